@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// A predetermined answer for a field that would otherwise be prompted for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Answer {
+    /// The value of the field as it would be entered manually.
+    ///
+    /// Lists, e.g. `authors` or `keywords`, are comma-separated.
+    Value(String),
+    /// Extract the value from `git config`.
+    FromGit,
+    /// Run `cargo-msrv` and use its result.
+    Msrv,
+    /// Default to `https://docs.rs/<package name>`.
+    DocsRs,
+    /// Leave the field as is.
+    Skip,
+}
+
+/// The fields that can be answered in advance.
+pub(crate) const FIELDS: &[&str] = &[
+    "authors",
+    "edition",
+    "rust-version",
+    "description",
+    "documentation",
+    "readme",
+    "homepage",
+    "repository",
+    "license",
+    "license-file",
+    "keywords",
+    "categories",
+];
+
+/// The fields whose values are lists.
+const LIST_FIELDS: &[&str] = &["authors", "keywords", "categories"];
+
+impl Answer {
+    /// Returns the value of the answer or an error if the field
+    /// can't be filled with the given strategy.
+    pub(crate) fn value(&self, field: &str) -> Result<&str> {
+        match self {
+            Answer::Value(value) => Ok(value),
+            _ => Err(self.unsupported(field)),
+        }
+    }
+
+    fn unsupported(&self, field: &str) -> Error {
+        Error::Usage(format!(
+            "`{}` is not a valid answer for the `{}` field",
+            self, field
+        ))
+    }
+
+    /// Parses the answer for the field, where the strategies are given by their names,
+    /// e.g. `from-git`.
+    pub(crate) fn parse(field: &str, answer: &str) -> Result<Self> {
+        let answer = match answer {
+            "from-git" => Answer::FromGit,
+            "msrv" => Answer::Msrv,
+            "docs-rs" => Answer::DocsRs,
+            "skip" => Answer::Skip,
+            value => return Ok(Answer::Value(value.to_string())),
+        };
+        let supported = match answer {
+            Answer::FromGit => ["authors", "repository"].contains(&field),
+            Answer::Msrv => field == "rust-version",
+            Answer::DocsRs => field == "documentation",
+            _ => true,
+        };
+        if !supported {
+            return Err(answer.unsupported(field));
+        }
+        Ok(answer)
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Answer::Value(value) => value,
+            Answer::FromGit => "from-git",
+            Answer::Msrv => "msrv",
+            Answer::DocsRs => "docs-rs",
+            Answer::Skip => "skip",
+        })
+    }
+}
+
+/// An answer as it is written in an answers file.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum RawAnswer {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl RawAnswer {
+    /// Converts the answer for the field, failing on unknown fields
+    /// and on strategies that the field doesn't support.
+    pub(crate) fn into_answer(self, field: &str) -> Result<Answer> {
+        if !FIELDS.contains(&field) {
+            return Err(Error::Usage(format!("unknown field `{}`", field)));
+        }
+        match self {
+            RawAnswer::One(answer) => Answer::parse(field, &answer),
+            RawAnswer::Many(values) => Ok(Answer::Value(values.join(", "))),
+        }
+    }
+}
+
+/// Answers keyed by the name of the field in `Cargo.toml`, e.g. `rust-version`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Answers(BTreeMap<String, Answer>);
+
+impl Answers {
+    pub(crate) fn get(&self, field: &str) -> Option<&Answer> {
+        self.0.get(field)
+    }
+
+    pub(crate) fn insert(&mut self, field: &str, answer: Answer) {
+        self.0.insert(field.to_string(), answer);
+    }
+
+    /// Adds the answers, replacing the existing answers for the same fields.
+    pub(crate) fn extend(&mut self, answers: Answers) {
+        self.0.extend(answers.0);
+    }
+
+    /// Reads an answers file.
+    ///
+    /// Files with the `.json` extension are read as JSON and the rest as TOML, e.g.
+    ///
+    /// ```toml
+    /// repository = "from-git"
+    /// license = "MIT OR Apache-2.0"
+    /// rust-version = "msrv"
+    /// keywords = ["cli", "cargo"]
+    /// ```
+    pub(crate) fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let invalid = |e: &dyn fmt::Display| {
+            Error::Usage(format!("invalid answers file {}: {}", path.display(), e))
+        };
+        let raw: BTreeMap<String, RawAnswer> = if is_json(path) {
+            serde_json::from_str(&contents).map_err(|e| invalid(&e))?
+        } else {
+            toml::from_str(&contents).map_err(|e| invalid(&e))?
+        };
+        let mut answers = Answers::default();
+        for (field, answer) in raw {
+            let answer = answer.into_answer(&field).map_err(|e| invalid(&e))?;
+            answers.insert(&field, answer);
+        }
+        Ok(answers)
+    }
+
+    /// Writes the answers in the format of [`Answers::read`].
+    pub(crate) fn write(&self, path: &Path) -> Result<()> {
+        let raw: BTreeMap<&str, RawAnswer> = self
+            .0
+            .iter()
+            .map(|(field, answer)| {
+                let raw = match answer {
+                    Answer::Value(value) if LIST_FIELDS.contains(&field.as_str()) => {
+                        RawAnswer::Many(split_comma_separated(value))
+                    }
+                    answer => RawAnswer::One(answer.to_string()),
+                };
+                (field.as_str(), raw)
+            })
+            .collect();
+        let contents = if is_json(path) {
+            serde_json::to_string_pretty(&raw).expect("the answers always serialize to JSON")
+        } else {
+            toml::to_string(&raw).expect("the answers always serialize to TOML")
+        };
+        std::fs::write(path, contents).map_err(|e| Error::io(path, e))
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+pub(crate) fn split_comma_separated(s: &str) -> Vec<String> {
+    s.split(',').map(|s| s.trim().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_answers_are_replayed() {
+        let mut answers = Answers::default();
+        answers.insert("authors", Answer::FromGit);
+        answers.insert("repository", Answer::FromGit);
+        answers.insert("rust-version", Answer::Msrv);
+        answers.insert("documentation", Answer::DocsRs);
+        answers.insert("homepage", Answer::Skip);
+        answers.insert("license", Answer::Value("MIT OR Apache-2.0".to_string()));
+        answers.insert("keywords", Answer::Value("cli, cargo".to_string()));
+
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["answers.toml", "answers.json"] {
+            let path = dir.path().join(file);
+            answers.write(&path).unwrap();
+            assert_eq!(Answers::read(&path).unwrap(), answers, "{}", file);
+        }
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("answers.toml")).unwrap(),
+            r#"authors = "from-git"
+documentation = "docs-rs"
+homepage = "skip"
+keywords = ["cli", "cargo"]
+license = "MIT OR Apache-2.0"
+repository = "from-git"
+rust-version = "msrv"
+"#
+        );
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use cargo_toml::{DepsSet, Inheritable};
+use serde::Deserialize;
+use spdx::{Expression, LicenseReq};
+
+use crate::error::{Error, Result};
+use crate::license::normalize_license;
+use crate::manifest::ManifestFile;
+use crate::policy::Policy;
+use crate::workspace::Workspace;
+
+/// The prefixes of the strong copyleft licenses, which extend to the works that depend on them.
+///
+/// The weak copyleft licenses, e.g. `MPL-2.0` or `LGPL-3.0-only`, only apply to the files
+/// or the library itself, so they don't conflict with the license of the package.
+const STRONG_COPYLEFT: &[&str] = &["AGPL-", "EUPL-", "GPL-", "OSL-", "SSPL-"];
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    /// Whether the entry of the `dependencies` list of another package refers to this package.
+    ///
+    /// The entries are `name` or, if several versions are locked, `name version`.
+    fn is_referred_to_by(&self, entry: &str) -> bool {
+        let mut parts = entry.split(' ');
+        parts.next() == Some(self.name.as_str())
+            && parts.next().is_none_or(|version| version == self.version)
+    }
+}
+
+/// A dependency whose license is not acceptable.
+#[derive(Debug)]
+pub(crate) struct Violation {
+    /// The name and the version of the dependency.
+    pub(crate) dependency: String,
+    pub(crate) license: String,
+    pub(crate) reason: String,
+    /// Whether the dependency is only needed by the dev-dependencies, so it isn't shipped.
+    pub(crate) is_dev_only: bool,
+}
+
+/// The result of the audit of the licenses of the dependencies.
+#[derive(Debug, Default)]
+pub(crate) struct Audit {
+    pub(crate) violations: Vec<Violation>,
+    /// The dependencies whose license couldn't be found in the local registry cache.
+    pub(crate) unknown: Vec<String>,
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Returns the `Cargo.lock` of the package or of its workspace, if any.
+///
+/// The search stops at the workspace root and at the root of the git repository,
+/// so that the lockfile of an unrelated project above isn't used.
+fn find_lockfile(manifest_dir: &Path) -> Option<PathBuf> {
+    for dir in manifest_dir.ancestors() {
+        let path = dir.join("Cargo.lock");
+        if path.is_file() {
+            return Some(path);
+        }
+        let is_workspace_root = ManifestFile::read(&dir.join("Cargo.toml"))
+            .and_then(|file| file.manifest())
+            .is_ok_and(|manifest| manifest.workspace.is_some());
+        if is_workspace_root || dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// Returns the license of the crate from its unpacked manifest in the registry cache.
+fn registry_license(cargo_home: &Path, name: &str, version: &str) -> Option<String> {
+    let registries = std::fs::read_dir(cargo_home.join("registry").join("src")).ok()?;
+    registries.flatten().find_map(|registry| {
+        let path = registry
+            .path()
+            .join(format!("{}-{}", name, version))
+            .join("Cargo.toml");
+        let contents = std::fs::read_to_string(path).ok()?;
+        let manifest: toml::Table = toml::from_str(&contents).ok()?;
+        let license = manifest.get("package")?.get("license")?.as_str()?;
+        Some(license.to_string())
+    })
+}
+
+fn is_strong_copyleft(req: &LicenseReq) -> bool {
+    req.license.id().is_some_and(|id| {
+        STRONG_COPYLEFT
+            .iter()
+            .any(|prefix| id.name.starts_with(prefix))
+    })
+}
+
+/// Whether the license can be chosen without a strong copyleft license.
+fn is_permissive(license: &Expression) -> bool {
+    license.evaluate(|req| !is_strong_copyleft(req))
+}
+
+/// Returns the names of the packages that the manifest only has as dev-dependencies.
+///
+/// Cargo.lock doesn't tell the kinds of the dependencies apart, so they are read from the manifest.
+fn dev_only_dependencies(manifest_dir: &Path) -> Result<Vec<String>> {
+    let manifest = ManifestFile::read(&manifest_dir.join("Cargo.toml"))?.manifest()?;
+    let names = |deps: &DepsSet| -> Vec<String> {
+        deps.iter()
+            .map(|(key, dependency)| dependency.package().unwrap_or(key).to_string())
+            .collect()
+    };
+    let mut dev = names(&manifest.dev_dependencies);
+    let mut shipped = names(&manifest.dependencies);
+    shipped.extend(names(&manifest.build_dependencies));
+    for target in manifest.target.values() {
+        dev.extend(names(&target.dev_dependencies));
+        shipped.extend(names(&target.dependencies));
+        shipped.extend(names(&target.build_dependencies));
+    }
+    dev.retain(|name| !shipped.contains(name));
+    Ok(dev)
+}
+
+/// Returns the locked packages that the entries of `dependencies` lists refer to,
+/// directly or transitively.
+fn resolve<'a>(lockfile: &'a Lockfile, entries: Vec<&'a str>) -> Vec<&'a LockedPackage> {
+    let mut visited = BTreeSet::new();
+    let mut resolved = Vec::new();
+    let mut stack = entries;
+    while let Some(entry) = stack.pop() {
+        for package in lockfile
+            .package
+            .iter()
+            .filter(|p| p.is_referred_to_by(entry))
+        {
+            if visited.insert((&package.name, &package.version)) {
+                stack.extend(package.dependencies.iter().map(String::as_str));
+                resolved.push(package);
+            }
+        }
+    }
+    resolved
+}
+
+/// Returns the locked registry packages that the package depends on, directly or
+/// transitively, and whether they are only needed by the given dev-dependencies.
+fn locked_dependencies<'a>(
+    lockfile: &'a Lockfile,
+    package_name: &str,
+    dev_only: &[String],
+) -> Vec<(&'a LockedPackage, bool)> {
+    let entries: Vec<&str> = lockfile
+        .package
+        .iter()
+        .filter(|package| package.name == package_name && package.source.is_none())
+        .flat_map(|package| package.dependencies.iter().map(String::as_str))
+        .collect();
+    let shipped_entries: Vec<&str> = entries
+        .iter()
+        .copied()
+        .filter(|entry| {
+            let name = entry.split(' ').next().unwrap_or(entry);
+            !dev_only.iter().any(|dev| dev == name)
+        })
+        .collect();
+    let shipped = resolve(lockfile, shipped_entries);
+    let mut dependencies: Vec<(&LockedPackage, bool)> = resolve(lockfile, entries)
+        .into_iter()
+        .filter(|dependency| {
+            dependency
+                .source
+                .as_deref()
+                .is_some_and(|source| source.starts_with("registry+"))
+        })
+        .map(|dependency| {
+            let is_dev_only = !shipped.iter().any(|p| std::ptr::eq(*p, dependency));
+            (dependency, is_dev_only)
+        })
+        .collect();
+    dependencies.sort_by(|(a, _), (b, _)| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    dependencies
+}
+
+/// Checks the licenses of the dependencies of the package against its license and
+/// the `allowed-dependency-licenses` of the policy, entirely offline.
+///
+/// The dependencies are read from `Cargo.lock` and their licenses from the manifests
+/// unpacked in `~/.cargo/registry/src`. Without `Cargo.lock`, nothing is checked.
+pub(crate) fn audit(
+    package_name: &str,
+    license: &str,
+    manifest_dir: &Path,
+    policy: &Policy,
+) -> Result<Audit> {
+    let mut audit = Audit::default();
+    let (Some(lockfile_path), Some(cargo_home)) = (find_lockfile(manifest_dir), cargo_home())
+    else {
+        return Ok(audit);
+    };
+    let contents =
+        std::fs::read_to_string(&lockfile_path).map_err(|e| Error::io(&lockfile_path, e))?;
+    let lockfile: Lockfile = toml::from_str(&contents).map_err(|e| {
+        Error::Usage(format!(
+            "invalid lockfile {}: {}",
+            lockfile_path.display(),
+            e
+        ))
+    })?;
+    let package_license = normalize_license(license)
+        .ok()
+        .and_then(|license| Expression::parse(&license).ok());
+    let package_is_permissive = package_license.as_ref().is_some_and(is_permissive);
+    let dev_only = dev_only_dependencies(manifest_dir)?;
+
+    for (dependency, is_dev_only) in locked_dependencies(&lockfile, package_name, &dev_only) {
+        let name = format!("{} {}", dependency.name, dependency.version);
+        let expression = registry_license(&cargo_home, &dependency.name, &dependency.version)
+            .and_then(|license| normalize_license(&license).ok())
+            .and_then(|license| Expression::parse(&license).ok());
+        let Some(expression) = expression else {
+            audit.unknown.push(name);
+            continue;
+        };
+        let dependency_license = expression.to_string();
+        if let Some(allowed) = policy.allowed_dependency_licenses() {
+            let is_allowed = expression.evaluate(|req| {
+                req.license
+                    .id()
+                    .is_some_and(|id| allowed.iter().any(|allowed| *allowed == id.name))
+            });
+            if !is_allowed {
+                audit.violations.push(Violation {
+                    dependency: name,
+                    license: dependency_license,
+                    reason: "is not allowed by the policy".to_string(),
+                    is_dev_only,
+                });
+                continue;
+            }
+        }
+        if package_is_permissive && !is_permissive(&expression) {
+            audit.violations.push(Violation {
+                dependency: name,
+                license: dependency_license,
+                reason: format!(
+                    "is a strong copyleft license that conflicts with `{}`",
+                    license
+                ),
+                is_dev_only,
+            });
+        }
+    }
+    Ok(audit)
+}
+
+/// Audits the dependencies of the package being filled or, for `[workspace.package]`,
+/// of the members that inherit the license from it, as the template itself
+/// has no dependencies.
+pub(crate) fn audit_filled(
+    package_name: &str,
+    license: &str,
+    manifest_dir: &Path,
+    policy: &Policy,
+) -> Result<Audit> {
+    let manifest_path = manifest_dir.join("Cargo.toml");
+    let manifest = ManifestFile::read(&manifest_path)?.manifest()?;
+    let is_package = manifest
+        .package
+        .as_ref()
+        .is_some_and(|package| package.name == package_name);
+    if is_package || manifest.workspace.is_none() {
+        return audit(package_name, license, manifest_dir, policy);
+    }
+    let mut audit_of_members = Audit::default();
+    for member in Workspace::find_required(&manifest_path)?.members {
+        let Some(package) = ManifestFile::read(&member)?.manifest()?.package else {
+            continue;
+        };
+        if matches!(package.license, Some(Inheritable::Set(_))) {
+            continue;
+        }
+        let dir = member.parent().expect("the manifest path has no parent");
+        let member_audit = audit(&package.name, license, dir, policy)?;
+        audit_of_members.merge(member_audit);
+    }
+    Ok(audit_of_members)
+}
+
+impl Violation {
+    pub(crate) fn message(&self) -> String {
+        let message = format!(
+            "`{}` is licensed under `{}`, which {}",
+            self.dependency, self.license, self.reason
+        );
+        if self.is_dev_only {
+            format!("{}, but it is only used by the dev-dependencies", message)
+        } else {
+            message
+        }
+    }
+}
+
+impl Audit {
+    /// Adds the findings of the audit of another package, without duplicates.
+    ///
+    /// A dependency counts as dev-only only if it is one for every package.
+    fn merge(&mut self, other: Audit) {
+        for violation in other.violations {
+            match self
+                .violations
+                .iter_mut()
+                .find(|v| v.dependency == violation.dependency)
+            {
+                Some(existing) => existing.is_dev_only &= violation.is_dev_only,
+                None => self.violations.push(violation),
+            }
+        }
+        for dependency in other.unknown {
+            if !self.unknown.contains(&dependency) {
+                self.unknown.push(dependency);
+            }
+        }
+    }
+
+    /// Prints the violations and how many dependencies couldn't be audited to stderr.
+    pub(crate) fn print(&self) {
+        for violation in &self.violations {
+            eprintln!("warning: {}.", violation.message());
+        }
+        if !self.unknown.is_empty() {
+            eprintln!(
+                "The licenses of {} dependencies are not in the local registry cache, \
+                run `cargo fetch` to audit them.",
+                self.unknown.len()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copyleft_licenses_are_detected() {
+        let permissive = |license| is_permissive(&Expression::parse(license).unwrap());
+        assert!(permissive("MIT OR Apache-2.0"));
+        assert!(permissive("MPL-2.0"));
+        assert!(permissive("GPL-3.0-only OR MIT"));
+        assert!(!permissive("GPL-3.0-only"));
+        assert!(!permissive("MIT AND AGPL-3.0-or-later"));
+    }
+
+    #[test]
+    fn dependencies_are_resolved_from_the_lockfile() {
+        let lockfile: Lockfile = toml::from_str(
+            r#"
+            [[package]]
+            name = "app"
+            version = "0.1.0"
+            dependencies = ["a", "b 2.0.0", "d"]
+
+            [[package]]
+            name = "a"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            dependencies = ["c"]
+
+            [[package]]
+            name = "b"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "b"
+            version = "2.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "c"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "d"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            dependencies = ["c", "e"]
+
+            [[package]]
+            name = "e"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            "#,
+        )
+        .unwrap();
+        // `d` is a dev-dependency, and `c` is also needed by `a`.
+        let dev_only = ["d".to_string()];
+        let dependencies: Vec<(&str, &str, bool)> =
+            locked_dependencies(&lockfile, "app", &dev_only)
+                .iter()
+                .map(|(package, is_dev_only)| {
+                    (
+                        package.name.as_str(),
+                        package.version.as_str(),
+                        *is_dev_only,
+                    )
+                })
+                .collect();
+        assert_eq!(
+            dependencies,
+            [
+                ("a", "1.0.0", false),
+                ("b", "2.0.0", false),
+                ("c", "1.0.0", false),
+                ("d", "1.0.0", true),
+                ("e", "1.0.0", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn lockfiles_are_searched_up_to_the_workspace_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, contents: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        // An unrelated project that the package happens to be in.
+        write("Cargo.lock", "");
+        write("project/Cargo.toml", "[workspace]\nmembers = [\"a\"]\n");
+        write("project/a/Cargo.toml", "[package]\nname = \"a\"\n");
+        write("repo/.git/HEAD", "");
+        write("repo/b/Cargo.toml", "[package]\nname = \"b\"\n");
+
+        assert_eq!(find_lockfile(&dir.path().join("project/a")), None);
+        assert_eq!(find_lockfile(&dir.path().join("repo/b")), None);
+        write("project/Cargo.lock", "");
+        assert_eq!(
+            find_lockfile(&dir.path().join("project/a")),
+            Some(dir.path().join("project/Cargo.lock"))
+        );
+    }
+}
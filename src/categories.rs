@@ -0,0 +1,790 @@
+use std::path::Path;
+
+use cargo_toml::{Manifest, Package};
+use promptly::prompt_opt;
+
+use crate::error::Result;
+use crate::validate::MAX_CATEGORIES;
+
+/// The category slugs accepted by crates.io with their descriptions,
+/// see <https://crates.io/category_slugs>.
+pub(crate) const CATEGORIES: &[(&str, &str)] = &[
+    (
+        "accessibility",
+        "Assistive technology that helps overcome disabilities and impairments to make software usable by as many people as possible.",
+    ),
+    (
+        "aerospace",
+        "Crates for aerospace, i.e. aeronautics and astronautics.",
+    ),
+    (
+        "aerospace::drones",
+        "Crates for unmanned aerial vehicles, i.e. drones.",
+    ),
+    (
+        "aerospace::protocols",
+        "Implementations of the protocols used in aerospace.",
+    ),
+    (
+        "aerospace::simulation",
+        "Simulations of aerospace vehicles and their environments.",
+    ),
+    (
+        "aerospace::space-protocols",
+        "Implementations of the protocols used in space, e.g. by satellites.",
+    ),
+    (
+        "aerospace::unmanned-aerial-vehicles",
+        "Crates for unmanned aerial vehicles.",
+    ),
+    (
+        "algorithms",
+        "Rust implementations of core algorithms such as hashing, sorting, searching, and more.",
+    ),
+    (
+        "api-bindings",
+        "Idiomatic wrappers of specific APIs for convenient access from Rust.",
+    ),
+    (
+        "asynchronous",
+        "Crates to help you deal with events independently of the main program flow, using techniques like futures, promises, waiting, or eventing.",
+    ),
+    (
+        "authentication",
+        "Crates to help with the process of confirming identities.",
+    ),
+    (
+        "caching",
+        "Crates to store the results of previous computations in order to reuse the results.",
+    ),
+    (
+        "command-line-interface",
+        "Crates to help create command line interfaces, such as argument parsers, line-editing, or output coloring and formatting.",
+    ),
+    (
+        "command-line-utilities",
+        "Applications to run at the command line.",
+    ),
+    (
+        "compilers",
+        "Compiler implementations, including interpreters and transpilers.",
+    ),
+    (
+        "compression",
+        "Algorithms for making data smaller.",
+    ),
+    (
+        "computer-vision",
+        "Crates for comprehending the world from video or images.",
+    ),
+    (
+        "concurrency",
+        "Crates for implementing concurrent and parallel computation.",
+    ),
+    (
+        "config",
+        "Crates to facilitate configuration management for applications.",
+    ),
+    (
+        "cryptography",
+        "Algorithms intended for securing data.",
+    ),
+    (
+        "cryptography::cryptocurrencies",
+        "Crates for digital currencies, wallets, and distributed ledgers.",
+    ),
+    (
+        "data-structures",
+        "Rust implementations of particular ways of organizing data suited for specific purposes.",
+    ),
+    (
+        "database",
+        "Crates to interface with database management systems.",
+    ),
+    (
+        "database-implementations",
+        "Database management systems implemented in Rust.",
+    ),
+    (
+        "date-and-time",
+        "Crates to manage the inherent complexity of dealing with the fourth dimension.",
+    ),
+    (
+        "development-tools",
+        "Crates that provide developer-facing features such as testing, debugging, linting, performance profiling, autocompletion, formatting, and more.",
+    ),
+    (
+        "development-tools::build-utils",
+        "Utilities for build scripts and other build time steps.",
+    ),
+    (
+        "development-tools::cargo-plugins",
+        "Subcommands that extend the capabilities of Cargo.",
+    ),
+    (
+        "development-tools::debugging",
+        "Crates to help you figure out what is going on with your code such as logging, tracing, or assertions.",
+    ),
+    (
+        "development-tools::ffi",
+        "Crates to help you better interface with other languages.",
+    ),
+    (
+        "development-tools::procedural-macro-helpers",
+        "Crates to help you write procedural macros in Rust.",
+    ),
+    (
+        "development-tools::profiling",
+        "Crates to help you figure out the performance of your code.",
+    ),
+    (
+        "development-tools::testing",
+        "Crates to help you verify the correctness of your code.",
+    ),
+    (
+        "email",
+        "Crates to help with sending, receiving, formatting, and parsing email.",
+    ),
+    (
+        "embedded",
+        "Crates that are primarily useful on embedded devices or without an operating system.",
+    ),
+    (
+        "emulators",
+        "Emulators that allow one computer to behave like another, often to allow running software that is not natively available on the host computer.",
+    ),
+    (
+        "encoding",
+        "Encoding and/or decoding data from one data format to another.",
+    ),
+    (
+        "external-ffi-bindings",
+        "Direct Rust FFI bindings to libraries written in other languages, often denoted by a -sys suffix.",
+    ),
+    (
+        "filesystem",
+        "Crates for dealing with files and filesystems.",
+    ),
+    (
+        "finance",
+        "Crates for dealing with money, accounting, trading, investments, taxes, banking and payment processing.",
+    ),
+    (
+        "game-development",
+        "Libraries and tools for building games.",
+    ),
+    (
+        "game-engines",
+        "Ready-to-go frameworks for building games.",
+    ),
+    (
+        "games",
+        "Applications for fun and entertainment.",
+    ),
+    (
+        "graphics",
+        "Crates for graphics libraries and applications, including raster and vector graphics primitives.",
+    ),
+    (
+        "gui",
+        "Crates to help you create a graphical user interface.",
+    ),
+    (
+        "hardware-support",
+        "Crates to interface with specific CPU or other hardware features.",
+    ),
+    (
+        "internationalization",
+        "Crates to develop software adapted to various languages and regions.",
+    ),
+    (
+        "localization",
+        "Crates to help create software adapted to a specific language and region.",
+    ),
+    (
+        "mathematics",
+        "Crates with a mathematical aspect.",
+    ),
+    (
+        "memory-management",
+        "Crates to help with allocation, memory mapping, garbage collection, reference counting, or interfaces to foreign memory managers.",
+    ),
+    (
+        "multimedia",
+        "Crates that provide audio, video, and image processing or rendering engines.",
+    ),
+    (
+        "multimedia::audio",
+        "Crates that record, output, or process audio.",
+    ),
+    (
+        "multimedia::encoding",
+        "Crates that encode or decode binary data in multimedia formats.",
+    ),
+    (
+        "multimedia::images",
+        "Crates that process or build images.",
+    ),
+    (
+        "multimedia::video",
+        "Crates that record, output, or process video.",
+    ),
+    (
+        "network-programming",
+        "Crates dealing with higher-level network protocols such as FTP, HTTP, or SSH, or lower-level network protocols such as TCP or UDP.",
+    ),
+    (
+        "no-std",
+        "Crates that are able to function without the Rust standard library.",
+    ),
+    (
+        "no-std::no-alloc",
+        "Crates that are able to function without the Rust alloc crate.",
+    ),
+    (
+        "os",
+        "Bindings to operating system-specific APIs.",
+    ),
+    (
+        "os::android-apis",
+        "Bindings to Android-specific APIs.",
+    ),
+    (
+        "os::freebsd-apis",
+        "Bindings to FreeBSD-specific APIs.",
+    ),
+    (
+        "os::linux-apis",
+        "Bindings to Linux-specific APIs.",
+    ),
+    (
+        "os::macos-apis",
+        "Bindings to macOS-specific APIs.",
+    ),
+    (
+        "os::unix-apis",
+        "Bindings to Unix-specific APIs.",
+    ),
+    (
+        "os::windows-apis",
+        "Bindings to Windows-specific APIs.",
+    ),
+    (
+        "parser-implementations",
+        "Parsers implemented for particular formats or languages.",
+    ),
+    (
+        "parsing",
+        "Crates to help create parsers of binary and text formats.",
+    ),
+    (
+        "rendering",
+        "Real-time or offline rendering of 2D or 3D graphics, usually with the help of a graphics card.",
+    ),
+    (
+        "rendering::data-formats",
+        "Loading and parsing of data formats related to 2D or 3D rendering, like 3D models or animation sheets.",
+    ),
+    (
+        "rendering::engine",
+        "High-level solutions for rendering on the screen.",
+    ),
+    (
+        "rendering::graphics-api",
+        "Crates that provide direct access to the hardware's or the operating system's rendering capabilities.",
+    ),
+    (
+        "rust-patterns",
+        "Shared solutions for particular situations specific to programming in Rust.",
+    ),
+    (
+        "science",
+        "Crates related to solving problems involving physics, chemistry, biology, machine learning, geoscience, and other scientific fields.",
+    ),
+    (
+        "science::bioinformatics",
+        "Crates for processing biological data.",
+    ),
+    (
+        "science::bioinformatics::genomics",
+        "Crates for the study of genomes.",
+    ),
+    (
+        "science::bioinformatics::proteomics",
+        "Crates for the study of proteins.",
+    ),
+    (
+        "science::bioinformatics::sequence-analysis",
+        "Crates for the analysis of DNA, RNA and protein sequences.",
+    ),
+    (
+        "science::geo",
+        "Processing of spatial information, maps, navigation, geography, and geosciences.",
+    ),
+    (
+        "science::neuroscience",
+        "Crates for the study of the nervous system.",
+    ),
+    (
+        "science::robotics",
+        "Crates related to robotics.",
+    ),
+    (
+        "simulation",
+        "Crates used to model or construct models for some activity, e.g. to simulate a networking protocol.",
+    ),
+    (
+        "template-engine",
+        "Crates designed to combine templates with data to produce result documents, usually with an emphasis on processing text.",
+    ),
+    (
+        "text-editors",
+        "Applications for editing text.",
+    ),
+    (
+        "text-processing",
+        "Crates to deal with the complexities of human language when expressed in textual form.",
+    ),
+    (
+        "value-formatting",
+        "Crates to allow an application to format values for display to a user, potentially adapting the display to various languages and regions.",
+    ),
+    (
+        "virtualization",
+        "Crates for creating and managing virtual environments, containers and virtual machines.",
+    ),
+    (
+        "visualization",
+        "Ways to view data, such as plotting or graphing.",
+    ),
+    (
+        "wasm",
+        "Crates for use when targeting WebAssembly, or for manipulating WebAssembly.",
+    ),
+    (
+        "web-programming",
+        "Crates to create applications for the web.",
+    ),
+    (
+        "web-programming::http-client",
+        "Crates to make HTTP network requests.",
+    ),
+    (
+        "web-programming::http-server",
+        "Crates to serve data over HTTP.",
+    ),
+    (
+        "web-programming::websocket",
+        "Crates to communicate over the WebSocket protocol.",
+    ),
+];
+
+/// The common names of the categories that are not close to their slugs.
+const ALIASES: &[(&str, &str)] = &[
+    ("a11y", "accessibility"),
+    ("async", "asynchronous"),
+    ("auth", "authentication"),
+    ("cargo", "development-tools::cargo-plugins"),
+    ("cargo-subcommand", "development-tools::cargo-plugins"),
+    ("cli", "command-line-utilities"),
+    ("configuration", "config"),
+    ("crypto", "cryptography"),
+    ("db", "database"),
+    ("ffi", "external-ffi-bindings"),
+    ("fs", "filesystem"),
+    ("gamedev", "game-development"),
+    ("i18n", "internationalization"),
+    ("l10n", "localization"),
+    ("logging", "development-tools::debugging"),
+    ("macros", "development-tools::procedural-macro-helpers"),
+    ("math", "mathematics"),
+    ("networking", "network-programming"),
+    ("no_std", "no-std"),
+    ("nostd", "no-std"),
+    ("parser", "parsing"),
+    ("proc-macro", "development-tools::procedural-macro-helpers"),
+    ("serialization", "encoding"),
+    ("sys", "external-ffi-bindings"),
+    ("terminal", "command-line-interface"),
+    ("time", "date-and-time"),
+    ("tui", "command-line-interface"),
+    ("web", "web-programming"),
+    ("webassembly", "wasm"),
+];
+
+pub(crate) fn is_category(slug: &str) -> bool {
+    CATEGORIES.iter().any(|(category, _)| *category == slug)
+}
+
+/// Returns the category that the unknown slug most likely refers to: the one with the slug
+/// as an alias or as its last part, e.g. `http-client`, or else the most similar one.
+pub(crate) fn suggest(slug: &str) -> Option<&'static str> {
+    let slug = slug.trim().to_lowercase().replace(' ', "-");
+    if let Some((_, category)) = ALIASES.iter().find(|(alias, _)| *alias == slug) {
+        return Some(category);
+    }
+    if let Some((category, _)) = CATEGORIES
+        .iter()
+        .find(|(category, _)| *category == slug || category.ends_with(&format!("::{}", slug)))
+    {
+        return Some(category);
+    }
+    CATEGORIES
+        .iter()
+        .map(|(category, _)| (strsim::jaro_winkler(&slug, category), *category))
+        .filter(|(similarity, _)| *similarity > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, category)| category)
+}
+
+/// Whether the source file declares `#![no_std]`, possibly behind `cfg_attr`.
+fn is_no_std(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|contents| declares_no_std(&contents))
+}
+
+/// Whether one of the inner attributes starting a line is `no_std`,
+/// e.g. `#![no_std]` or `#![cfg_attr(not(feature = "std"), no_std)]`.
+fn declares_no_std(contents: &str) -> bool {
+    let mut offset = 0;
+    contents.split_inclusive('\n').any(|line| {
+        let start = offset + line.len() - line.trim_start().len();
+        offset += line.len();
+        contents[start..]
+            .strip_prefix("#![")
+            .and_then(|attr| split_arguments(attr).into_iter().next())
+            .is_some_and(is_no_std_attribute)
+    })
+}
+
+/// Whether the attribute is `no_std` or a `cfg_attr` that applies it.
+fn is_no_std_attribute(attr: &str) -> bool {
+    let attr = attr.trim();
+    if attr == "no_std" {
+        return true;
+    }
+    let Some(args) = attr
+        .strip_prefix("cfg_attr")
+        .and_then(|args| args.trim_start().strip_prefix('('))
+    else {
+        return false;
+    };
+    // The first argument is the predicate, the rest are the attributes applied by it.
+    split_arguments(args)
+        .into_iter()
+        .skip(1)
+        .any(is_no_std_attribute)
+}
+
+/// Splits the comma-separated arguments up to the closing bracket
+/// that ends them, skipping the nested brackets and the strings.
+///
+/// Returns no arguments if the closing bracket is missing.
+fn split_arguments(s: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, c) in s.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 0 => {
+                args.push(&s[start..i]);
+                args.retain(|arg| !arg.trim().is_empty());
+                return args;
+            }
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    Vec::new()
+}
+
+/// Guesses the categories of the package from its manifest and its sources.
+pub(crate) fn detect_categories(package: &Package, dir: &Path) -> Vec<&'static str> {
+    let mut categories = Vec::new();
+    // The manifest is completed with the targets found by their default paths.
+    let Ok(manifest) = Manifest::from_path(dir.join("Cargo.toml")) else {
+        return categories;
+    };
+    let lib_path = manifest.lib.as_ref().and_then(|lib| lib.path.as_deref());
+    let root = match &manifest.lib {
+        Some(_) => dir.join(lib_path.unwrap_or("src/lib.rs")),
+        None => dir.join("src").join("main.rs"),
+    };
+    if is_no_std(&root) {
+        categories.push("no-std");
+    }
+    if manifest.lib.as_ref().is_some_and(|lib| lib.proc_macro) {
+        categories.push("development-tools::procedural-macro-helpers");
+    }
+    let bin_names = manifest.bin.iter().filter_map(|bin| bin.name.as_deref());
+    if bin_names.clone().any(|name| name.starts_with("cargo-")) {
+        categories.push("development-tools::cargo-plugins");
+    }
+    if manifest.lib.is_none() && !manifest.bin.is_empty() {
+        categories.push("command-line-utilities");
+    }
+    let has_wasm_bindgen = manifest.dependencies.contains_key("wasm-bindgen")
+        || manifest
+            .target
+            .values()
+            .any(|target| target.dependencies.contains_key("wasm-bindgen"));
+    if has_wasm_bindgen {
+        categories.push("wasm");
+    }
+    if package.name.ends_with("-sys") && package.links.is_some() {
+        categories.push("external-ffi-bindings");
+    }
+    categories.truncate(MAX_CATEGORIES);
+    categories
+}
+
+/// Returns the direct subcategories of the category, or the top-level categories.
+fn subcategories(parent: Option<&str>) -> Vec<(&'static str, &'static str)> {
+    CATEGORIES
+        .iter()
+        .copied()
+        .filter(|(category, _)| category.rsplit_once("::").map(|(p, _)| p) == parent)
+        .collect()
+}
+
+/// Lets the user browse the category hierarchy and toggle up to `MAX_CATEGORIES` categories,
+/// starting with the given ones selected.
+pub(crate) fn browse_categories(preselected: &[&'static str]) -> Result<Vec<String>> {
+    let mut selected = preselected.to_vec();
+    // The opened categories, from the top level down.
+    let mut path: Vec<&'static str> = Vec::new();
+    let mut is_listed = false;
+    loop {
+        let parent = path.last().copied();
+        let entries = subcategories(parent);
+        if !is_listed {
+            match parent {
+                Some(parent) => println!("The subcategories of `{}`:", parent),
+                None => println!("The categories:"),
+            }
+            for (i, (category, description)) in entries.iter().enumerate() {
+                let mark = if selected.contains(category) {
+                    "*"
+                } else {
+                    " "
+                };
+                let name = category.rsplit("::").next().unwrap_or(category);
+                let count = subcategories(Some(category)).len();
+                let more = match count {
+                    0 => String::new(),
+                    1 => " (+1 subcategory)".to_string(),
+                    count => format!(" (+{} subcategories)", count),
+                };
+                println!("{}{}. {}{}: {}", mark, i + 1, name, more, description);
+            }
+            if !selected.is_empty() {
+                println!("Selected: {}.", selected.join(", "));
+            }
+            is_listed = true;
+        }
+        let input: Option<String> = prompt_opt(
+            "Please enter the numbers of the categories to toggle, `>N` to open the subcategories \
+            of N, `<` to go back or nothing to finish",
+        )?;
+        let input = input.as_deref().unwrap_or_default().trim();
+        let entry = |number: &str| {
+            let entry = number
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| entries.get(i));
+            if entry.is_none() {
+                println!("There is no category number {}.", number.trim());
+            }
+            entry.map(|(category, _)| *category)
+        };
+        if input.is_empty() {
+            break;
+        } else if input == "<" {
+            if path.pop().is_some() {
+                is_listed = false;
+            } else {
+                println!("The top-level categories are already shown.");
+            }
+        } else if let Some(number) = input.strip_prefix('>') {
+            match entry(number) {
+                Some(category) if subcategories(Some(category)).is_empty() => {
+                    println!("`{}` has no subcategories.", category);
+                }
+                Some(category) => {
+                    path.push(category);
+                    is_listed = false;
+                }
+                None => {}
+            }
+        } else {
+            for number in input.split([',', ' ']).filter(|s| !s.is_empty()) {
+                let Some(category) = entry(number) else {
+                    continue;
+                };
+                if let Some(i) = selected.iter().position(|s| *s == category) {
+                    selected.remove(i);
+                } else if selected.len() < MAX_CATEGORIES {
+                    selected.push(category);
+                } else {
+                    println!(
+                        "At most {} categories can be selected, deselect one first.",
+                        MAX_CATEGORIES
+                    );
+                }
+            }
+            println!("Selected: {}.", selected.join(", "));
+        }
+    }
+    Ok(selected.into_iter().map(str::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories_are_suggested() {
+        assert_eq!(suggest("cli"), Some("command-line-utilities"));
+        assert_eq!(suggest("http-client"), Some("web-programming::http-client"));
+        assert_eq!(suggest("No-Std"), Some("no-std"));
+        assert_eq!(suggest("cryptograpy"), Some("cryptography"));
+        assert_eq!(suggest("zzz"), None);
+        assert!(ALIASES.iter().all(|(_, category)| is_category(category)));
+    }
+
+    #[test]
+    fn categories_form_a_tree() {
+        let top_level = subcategories(None);
+        assert!(top_level
+            .iter()
+            .all(|(category, _)| !category.contains("::")));
+        let names: Vec<&str> = subcategories(Some("science::bioinformatics"))
+            .iter()
+            .map(|(category, _)| *category)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "science::bioinformatics::genomics",
+                "science::bioinformatics::proteomics",
+                "science::bioinformatics::sequence-analysis",
+            ]
+        );
+    }
+
+    #[test]
+    fn no_std_is_parsed() {
+        for contents in [
+            "#![no_std]\n",
+            "//! Docs.\n\n  #![ no_std ]\nfn f() {}\n",
+            "#![cfg_attr(not(feature = \"std\"), no_std)]\n",
+            "#![cfg_attr(not(test), no_std,)]\n",
+            "#![cfg_attr(\n    not(any(feature = \"std\", test)),\n    no_std\n)]\n",
+            "#![cfg_attr(not(feature = \"std\"), deny(warnings), no_std)]\n",
+            "#![cfg_attr(docsrs, cfg_attr(not(test), no_std))]\n",
+        ] {
+            assert!(declares_no_std(contents), "{:?}", contents);
+        }
+        for contents in [
+            "#![cfg_attr(not(feature = \"no_std_compat\"), deny(warnings))]\n",
+            "#![cfg_attr(no_std, deny(warnings))]\n",
+            "#![doc = \"Works in no_std, see #![no_std].\"]\n",
+            "#![no_std_compat]\n",
+            "//! Add `#![no_std]` to the crate.\n",
+            "// #![no_std]\n",
+            "#![cfg_attr(not(test), no_std\n",
+        ] {
+            assert!(!declares_no_std(contents), "{:?}", contents);
+        }
+    }
+
+    /// Writes the files of a package and detects its categories.
+    fn detect(files: &[(&str, &str)]) -> Vec<&'static str> {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        let package = Manifest::from_path(dir.path().join("Cargo.toml"))
+            .unwrap()
+            .package
+            .unwrap();
+        detect_categories(&package, dir.path())
+    }
+
+    #[test]
+    fn categories_are_detected() {
+        let manifest = "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n";
+        assert_eq!(
+            detect(&[("Cargo.toml", manifest), ("src/lib.rs", "")]),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            detect(&[
+                ("Cargo.toml", manifest),
+                (
+                    "src/lib.rs",
+                    "#![cfg_attr(not(feature = \"no_std_compat\"), deny(missing_docs))]\n",
+                ),
+            ]),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            detect(&[
+                ("Cargo.toml", &manifest.replace("foo", "cargo-foo")),
+                ("src/main.rs", "fn main() {}\n"),
+            ]),
+            ["development-tools::cargo-plugins", "command-line-utilities"]
+        );
+
+        // Every rule that can apply to a library at once, which stays within the limit.
+        let manifest = r#"[package]
+name = "foo-sys"
+version = "0.1.0"
+links = "foo"
+
+[lib]
+proc-macro = true
+
+[[bin]]
+name = "cargo-foo"
+path = "src/main.rs"
+
+[target.'cfg(target_arch = "wasm32")'.dependencies]
+wasm-bindgen = "0.2"
+"#;
+        let detected = detect(&[
+            ("Cargo.toml", manifest),
+            ("src/lib.rs", "//! Bindings.\n#![no_std]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ]);
+        assert_eq!(
+            detected,
+            [
+                "no-std",
+                "development-tools::procedural-macro-helpers",
+                "development-tools::cargo-plugins",
+                "wasm",
+                "external-ffi-bindings",
+            ]
+        );
+        assert_eq!(detected.len(), MAX_CATEGORIES);
+    }
+}
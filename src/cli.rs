@@ -0,0 +1,307 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::answers::{Answer, Answers};
+use crate::report::Format;
+
+/// An interactive CLI tool for filling the fields of `Cargo.toml` quickly.
+///
+/// The fields given on the command line are filled without prompting.
+#[derive(Parser, Debug)]
+#[command(name = "cargo-fill", bin_name = "cargo fill", version)]
+pub(crate) struct Args {
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    /// Path to Cargo.toml.
+    #[arg(long, value_name = "PATH", global = true)]
+    pub(crate) manifest_path: Option<PathBuf>,
+
+    /// Answer "yes" to all confirmations and don't prompt for the fields
+    /// that are not given on the command line.
+    #[arg(short, long)]
+    pub(crate) yes: bool,
+
+    /// Ask about every field, including the ones that are already set,
+    /// instead of only filling the gaps.
+    #[arg(short, long)]
+    pub(crate) all: bool,
+
+    /// Print the diff of the changes and exit without saving them.
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+
+    /// Read the answers for the fields from a TOML or JSON file.
+    ///
+    /// The file maps the field names to values or strategies, e.g.
+    /// `repository = "from-git"`. The fields given on the command line
+    /// take precedence.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) answers: Option<PathBuf>,
+
+    /// Save the answers given during the session to a TOML or JSON file
+    /// that can be passed to `--answers`.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) record: Option<PathBuf>,
+
+    /// Fill all members of the workspace.
+    ///
+    /// This is the default for virtual manifests.
+    #[arg(long)]
+    pub(crate) workspace: bool,
+
+    /// Fill only the given workspace members.
+    #[arg(short, long = "package", value_name = "NAME")]
+    pub(crate) packages: Vec<String>,
+
+    #[command(flatten)]
+    pub(crate) fields: FieldArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Report the missing and invalid metadata without prompting.
+    ///
+    /// Exits with `1` if any errors are found.
+    Check(CheckArgs),
+    /// Fill the missing fields that can be derived with confidence, without prompting.
+    ///
+    /// Fills `authors` from git config, `documentation` with docs.rs for library crates,
+    /// `readme` with an existing README file, `repository` from the git remote and
+    /// `license-file` with a detected license file. The other fields are left alone.
+    Fix(FixArgs),
+    /// Offer to unify the metadata that differs between the workspace members.
+    ///
+    /// Compares the license, repository, authors, `rust-version`, edition and keywords
+    /// and prompts for the value of each mismatched field, which is then written to
+    /// every member.
+    Unify(UnifyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CheckArgs {
+    /// Check all members of the workspace and report the mismatches between them.
+    ///
+    /// This is the default for virtual manifests.
+    #[arg(long)]
+    pub(crate) workspace: bool,
+
+    /// Check only the given workspace members.
+    #[arg(short, long = "package", value_name = "NAME")]
+    pub(crate) packages: Vec<String>,
+
+    /// The format of the findings.
+    #[arg(long, value_enum, default_value_t = Format::Human)]
+    pub(crate) format: Format,
+
+    /// Write the findings to a file instead of the standard output.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct FixArgs {
+    /// Fix all members of the workspace.
+    ///
+    /// This is the default for virtual manifests.
+    #[arg(long)]
+    pub(crate) workspace: bool,
+
+    /// Fix only the given workspace members.
+    #[arg(short, long = "package", value_name = "NAME")]
+    pub(crate) packages: Vec<String>,
+
+    /// Print the diff of the changes and exit without saving them.
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct UnifyArgs {
+    /// Compare only the given workspace members.
+    #[arg(short, long = "package", value_name = "NAME")]
+    pub(crate) packages: Vec<String>,
+
+    /// Print the diff of the changes and exit without saving them.
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Fields")]
+pub(crate) struct FieldArgs {
+    /// Comma-separated authors, e.g. `Jane Doe <jane@example.com>`.
+    #[arg(long, value_name = "AUTHORS")]
+    authors: Option<String>,
+    /// Extract a single author from `git config`.
+    #[arg(long, conflicts_with = "authors")]
+    authors_from_git: bool,
+    /// The Rust edition, e.g. `2021`.
+    #[arg(long, value_name = "EDITION")]
+    edition: Option<String>,
+    /// The minimal supported Rust version, e.g. `1.54.0`.
+    #[arg(long, value_name = "VERSION")]
+    rust_version: Option<String>,
+    /// Run `cargo-msrv` and fill `rust-version` with the result.
+    #[arg(long, conflicts_with = "rust_version")]
+    rust_version_from_msrv: bool,
+    /// A description of the package.
+    #[arg(long, value_name = "TEXT")]
+    description: Option<String>,
+    /// The URL of the package documentation.
+    #[arg(long, value_name = "URL")]
+    documentation: Option<String>,
+    /// Default `documentation` to `https://docs.rs/<package name>`.
+    #[arg(long, conflicts_with = "documentation")]
+    documentation_from_docs_rs: bool,
+    /// Path to the package's README file.
+    #[arg(long, value_name = "PATH")]
+    readme: Option<String>,
+    /// URL of the package homepage.
+    #[arg(long, value_name = "URL")]
+    homepage: Option<String>,
+    /// URL of the package source repository.
+    #[arg(long, value_name = "URL")]
+    repository: Option<String>,
+    /// Extract `repository` with `git config --get remote.origin.url`.
+    #[arg(long, conflicts_with = "repository")]
+    repository_from_git: bool,
+    /// The package license as an SPDX expression, e.g. `MIT OR Apache-2.0`.
+    #[arg(long, value_name = "EXPR")]
+    license: Option<String>,
+    /// Path to the text of the license.
+    #[arg(long, value_name = "PATH")]
+    license_file: Option<String>,
+    /// Comma-separated keywords.
+    #[arg(long, value_name = "KEYWORDS")]
+    keywords: Option<String>,
+    /// Comma-separated category slugs.
+    #[arg(long, value_name = "SLUGS")]
+    categories: Option<String>,
+}
+
+impl FieldArgs {
+    pub(crate) fn answers(&self) -> Answers {
+        let mut answers = Answers::default();
+        let values = [
+            ("authors", &self.authors),
+            ("edition", &self.edition),
+            ("rust-version", &self.rust_version),
+            ("description", &self.description),
+            ("documentation", &self.documentation),
+            ("readme", &self.readme),
+            ("homepage", &self.homepage),
+            ("repository", &self.repository),
+            ("license", &self.license),
+            ("license-file", &self.license_file),
+            ("keywords", &self.keywords),
+            ("categories", &self.categories),
+        ];
+        for (field, value) in values {
+            if let Some(value) = value {
+                answers.insert(field, Answer::Value(value.clone()));
+            }
+        }
+        let strategies = [
+            ("authors", self.authors_from_git, Answer::FromGit),
+            ("rust-version", self.rust_version_from_msrv, Answer::Msrv),
+            (
+                "documentation",
+                self.documentation_from_docs_rs,
+                Answer::DocsRs,
+            ),
+            ("repository", self.repository_from_git, Answer::FromGit),
+        ];
+        for (field, enabled, answer) in strategies {
+            if enabled {
+                answers.insert(field, answer);
+            }
+        }
+        answers
+    }
+}
+
+impl Args {
+    /// Parses the command-line arguments.
+    ///
+    /// When the tool is invoked as `cargo fill`, cargo runs `cargo-fill fill <args>`,
+    /// so the leading `fill` argument is skipped. Running `cargo-fill <args>` directly
+    /// works as well.
+    pub(crate) fn parse_args() -> Self {
+        Self::parse_from(strip_subcommand(std::env::args_os().collect()))
+    }
+}
+
+/// Removes the `fill` argument that cargo passes after the binary name.
+fn strip_subcommand(mut args: Vec<OsString>) -> Vec<OsString> {
+    if args.get(1).is_some_and(|arg| arg == "fill") {
+        args.remove(1);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        let args = ["cargo-fill"]
+            .iter()
+            .chain(args)
+            .map(OsString::from)
+            .collect();
+        Args::try_parse_from(strip_subcommand(args)).unwrap()
+    }
+
+    #[test]
+    fn the_fill_argument_of_cargo_is_stripped() {
+        for args in [&["fill", "--yes"][..], &["--yes"]] {
+            let args = parse(args);
+            assert!(args.yes);
+            assert!(args.command.is_none());
+        }
+        assert!(matches!(
+            parse(&["fill", "check"]).command,
+            Some(Command::Check(_))
+        ));
+        // Only the first argument is the one added by cargo.
+        assert!(Args::try_parse_from(strip_subcommand(
+            ["cargo-fill", "--yes", "fill"].map(OsString::from).to_vec()
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn every_field_flag_is_an_answer() {
+        for field in crate::answers::FIELDS {
+            let args = parse(&[&format!("--{}", field), "x"]);
+            assert_eq!(
+                args.fields.answers().get(field),
+                Some(&Answer::Value("x".to_string())),
+                "{}",
+                field
+            );
+        }
+        let strategy_flags: Vec<String> = Args::command()
+            .get_arguments()
+            .filter_map(|arg| arg.get_long())
+            .filter(|long| long.contains("-from-"))
+            .map(str::to_string)
+            .collect();
+        assert_eq!(strategy_flags.len(), 4);
+        for flag in strategy_flags {
+            let field = &flag[..flag.find("-from-").unwrap()];
+            let answers = parse(&[&format!("--{}", flag)]).fields.answers();
+            assert!(
+                matches!(answers.get(field), Some(answer) if !matches!(answer, Answer::Value(_))),
+                "--{} doesn't answer `{}`",
+                flag,
+                field
+            );
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::answers::{Answer, RawAnswer};
+use crate::error::{Error, Result};
+
+/// The default of a field, offered as the first option of its menu.
+#[derive(Debug, Clone)]
+pub(crate) struct FieldDefault {
+    pub(crate) answer: Answer,
+    /// Shown next to the option, e.g. `recommended`.
+    pub(crate) label: Option<String>,
+}
+
+/// The defaults used when the user config doesn't override them.
+const BUILTIN_DEFAULTS: &[(&str, &str, Option<&str>)] = &[
+    ("edition", "2024", Some("latest")),
+    ("rust-version", "skip", Some("default")),
+    ("documentation", "docs-rs", None),
+    ("readme", "README.md", None),
+    ("license", "MIT OR Apache-2.0", Some("permissive")),
+    ("license-file", "LICENSE", None),
+];
+
+/// A default as it is written in the config file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDefault {
+    Answer(RawAnswer),
+    Labeled {
+        value: RawAnswer,
+        label: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    #[serde(default)]
+    defaults: BTreeMap<String, RawDefault>,
+}
+
+/// The user-level configuration.
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    defaults: BTreeMap<String, FieldDefault>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let defaults = BUILTIN_DEFAULTS
+            .iter()
+            .map(|(field, answer, label)| {
+                let default = FieldDefault {
+                    answer: Answer::parse(field, answer).expect("the builtin defaults are valid"),
+                    label: label.map(str::to_string),
+                };
+                (field.to_string(), default)
+            })
+            .collect();
+        Self { defaults }
+    }
+}
+
+impl Config {
+    /// The path to the user config,
+    /// i.e. `$XDG_CONFIG_HOME/cargo-fill/config.toml` or `~/.config/cargo-fill/config.toml`.
+    pub(crate) fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::home_dir().map(|home| home.join(".config")))?;
+        Some(config_dir.join("cargo-fill").join("config.toml"))
+    }
+
+    /// Loads the user config, if it exists, on top of the builtin defaults.
+    pub(crate) fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::read(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Reads the config file, e.g.
+    ///
+    /// ```toml
+    /// [defaults]
+    /// authors = "from-git"
+    /// license = { value = "MIT OR Apache-2.0", label = "recommended" }
+    /// homepage = "https://example.com/{name}"
+    /// ```
+    ///
+    /// The defaults are given like in the answers files.
+    /// `{name}` in the values is replaced with the name of the package.
+    fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let invalid = |e: &dyn std::fmt::Display| {
+            Error::Usage(format!("invalid config file {}: {}", path.display(), e))
+        };
+        let raw: RawConfig = toml::from_str(&contents).map_err(|e| invalid(&e))?;
+        let mut config = Self::default();
+        for (field, default) in raw.defaults {
+            let (answer, label) = match default {
+                RawDefault::Answer(answer) => (answer, None),
+                RawDefault::Labeled { value, label } => (value, label),
+            };
+            let answer = answer.into_answer(&field).map_err(|e| invalid(&e))?;
+            config
+                .defaults
+                .insert(field, FieldDefault { answer, label });
+        }
+        Ok(config)
+    }
+
+    /// Returns the default of the field for the package with the given name.
+    pub(crate) fn field_default(&self, field: &str, package_name: &str) -> Option<FieldDefault> {
+        let mut default = self.defaults.get(field)?.clone();
+        if let Answer::Value(value) = &mut default.answer {
+            *value = value.replace("{name}", package_name);
+        }
+        Some(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(contents: &str) -> Result<Config> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, contents).unwrap();
+        Config::read(&path)
+    }
+
+    #[test]
+    fn the_builtin_defaults_are_valid() {
+        let config = Config::default();
+        for (field, _, _) in BUILTIN_DEFAULTS {
+            assert!(config.field_default(field, "a").is_some(), "{}", field);
+        }
+        let default = config.field_default("documentation", "a").unwrap();
+        assert_eq!(default.answer, Answer::DocsRs);
+        assert_eq!(default.label, None);
+    }
+
+    #[test]
+    fn the_config_overrides_the_builtin_defaults() {
+        let config = read(
+            r#"
+[defaults]
+authors = "from-git"
+license = { value = "MIT", label = "recommended" }
+homepage = "https://example.com/{name}"
+"#,
+        )
+        .unwrap();
+        let default = |field| config.field_default(field, "foo").unwrap();
+        assert_eq!(default("authors").answer, Answer::FromGit);
+        assert_eq!(default("license").answer, Answer::Value("MIT".to_string()));
+        assert_eq!(default("license").label.as_deref(), Some("recommended"));
+        assert_eq!(
+            default("homepage").answer,
+            Answer::Value("https://example.com/foo".to_string())
+        );
+        // The fields the config doesn't mention keep their builtin defaults.
+        assert_eq!(default("edition").answer, Answer::Value("2024".to_string()));
+        assert_eq!(default("edition").label.as_deref(), Some("latest"));
+        assert!(config.field_default("description", "foo").is_none());
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        for contents in [
+            "[defaults]\nlicense = \"from-git\"\n",
+            "[defaults]\nunknown-field = \"x\"\n",
+            "[other]\n",
+        ] {
+            let e = read(contents).unwrap_err();
+            assert!(
+                matches!(&e, Error::Usage(msg) if msg.starts_with("invalid config file")),
+                "{:?}: {}",
+                contents,
+                e
+            );
+        }
+    }
+}
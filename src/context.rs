@@ -0,0 +1,307 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use cargo_toml::Package;
+use promptly::prompt;
+
+use crate::answers::{Answer, Answers};
+use crate::config::{Config, FieldDefault};
+use crate::error::{Error, Result};
+use crate::manifest::{is_inherited_value, package_field, ManifestFile};
+use crate::policy::Policy;
+
+pub(crate) type Filler = fn(&mut Package, &Context) -> Result<()>;
+
+/// The state shared by the fillers.
+#[derive(Clone)]
+pub(crate) struct Context {
+    answers: Answers,
+    config: Rc<Config>,
+    yes: bool,
+    all: bool,
+    dry_run: bool,
+    manifest_dir: PathBuf,
+    policy: Rc<Policy>,
+    recorder: Rc<RefCell<Recorder>>,
+    /// The files to write once the manifest is saved, with their contents.
+    pending_writes: Rc<RefCell<Vec<(PathBuf, String)>>>,
+}
+
+/// The answers given during the session, shared by the contexts of all manifests.
+#[derive(Default)]
+struct Recorder {
+    answers: Answers,
+    /// The strategy the last filler used, if any.
+    strategy: Option<Answer>,
+}
+
+impl Context {
+    pub(crate) fn new(
+        answers: Answers,
+        config: Config,
+        yes: bool,
+        all: bool,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            answers,
+            config: Rc::new(config),
+            yes,
+            all,
+            dry_run,
+            manifest_dir: PathBuf::from("."),
+            policy: Rc::default(),
+            recorder: Rc::default(),
+            pending_writes: Rc::default(),
+        }
+    }
+
+    /// Returns the context for filling the manifest at the given path,
+    /// with the policy that applies to it.
+    pub(crate) fn for_manifest(&self, manifest_path: &Path) -> Result<Self> {
+        let mut ctx = self.clone();
+        ctx.manifest_dir = manifest_path
+            .parent()
+            .expect("the manifest path has no parent")
+            .to_path_buf();
+        ctx.policy = Rc::new(Policy::find(&ctx.manifest_dir)?);
+        Ok(ctx)
+    }
+
+    /// Returns the context with the predetermined answers replaced.
+    pub(crate) fn with_answers(&self, answers: Answers) -> Self {
+        let mut ctx = self.clone();
+        ctx.answers = answers;
+        ctx
+    }
+
+    /// The policy for the manifest being filled.
+    pub(crate) fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// The directory of the manifest being filled.
+    ///
+    /// External tools, e.g. `git` and `cargo-msrv`, are run in it.
+    pub(crate) fn manifest_dir(&self) -> &Path {
+        &self.manifest_dir
+    }
+
+    /// Runs the filler for the field.
+    ///
+    /// Unless `--all` was given, the fields that are already set in the manifest are skipped.
+    /// Otherwise, the current value is shown and can be kept.
+    /// The fields with a predetermined answer are always filled.
+    pub(crate) fn fill(
+        &self,
+        file: &ManifestFile,
+        package: &mut Package,
+        field: &str,
+        filler: Filler,
+    ) -> Result<()> {
+        // `Package` has defaults for some fields, e.g. `edition`, so whether the field
+        // is set is decided by the manifest itself.
+        let current = file
+            .package_item(field)
+            .and_then(|_| package_field(package, field));
+        self.fill_field(package, field, current, filler)
+    }
+
+    /// Like [`Context::fill`] but with the current value of the field given explicitly.
+    ///
+    /// The fields inherited from `[workspace.package]`, the fields answered
+    /// with `skip` and the unanswered fields that the policy never asks about
+    /// are never filled.
+    pub(crate) fn fill_field(
+        &self,
+        package: &mut Package,
+        field: &str,
+        current: Option<toml::Value>,
+        filler: Filler,
+    ) -> Result<()> {
+        match self.answer(field) {
+            Some(Answer::Skip) => return Ok(()),
+            None if self.policy.never_asks(field) => return Ok(()),
+            _ => {}
+        }
+        if let Some(current) = current {
+            if is_inherited_value(&current) {
+                return Ok(());
+            }
+            if self.answer(field).is_none() {
+                if !self.all || !self.is_interactive() {
+                    return Ok(());
+                }
+                println!("The `{}` field is currently set to {}.", field, current);
+                if prompt("Keep the current value? (Y/n)")? {
+                    println!();
+                    return Ok(());
+                }
+            }
+        }
+        let before = package_field(package, field);
+        self.recorder.borrow_mut().strategy = None;
+        filler(package, self)?;
+        if self.answer(field).is_none() && !self.is_interactive() {
+            // The field wasn't asked about, so there is nothing to record.
+            return Ok(());
+        }
+        let mut recorder = self.recorder.borrow_mut();
+        let answer = match (self.answer(field), recorder.strategy.take()) {
+            (Some(answer), _) => answer.clone(),
+            (None, Some(strategy)) => strategy,
+            (None, None) => match package_field(package, field) {
+                Some(after) if Some(&after) != before.as_ref() => {
+                    Answer::Value(value_answer(after))
+                }
+                _ => Answer::Skip,
+            },
+        };
+        recorder.answers.insert(field, answer);
+        Ok(())
+    }
+
+    /// Records that the current field was filled with the strategy rather than
+    /// a value, so that replaying the answers uses the strategy again.
+    pub(crate) fn used_strategy(&self, strategy: Answer) {
+        self.recorder.borrow_mut().strategy = Some(strategy);
+    }
+
+    /// The answers given so far, for `--record`.
+    pub(crate) fn recorded_answers(&self) -> Answers {
+        self.recorder.borrow().answers.clone()
+    }
+
+    /// Returns the predetermined answer for the field, if any.
+    ///
+    /// The values fixed by the policy take precedence over the given answers.
+    pub(crate) fn answer(&self, field: &str) -> Option<&Answer> {
+        self.policy.fixed(field).or_else(|| self.answers.get(field))
+    }
+
+    /// Returns the default of the field from the config, offered first in its menu.
+    pub(crate) fn field_default(&self, field: &str, package_name: &str) -> Option<FieldDefault> {
+        self.config.field_default(field, package_name)
+    }
+
+    /// Whether the fields without a predetermined answer should be prompted for.
+    pub(crate) fn is_interactive(&self) -> bool {
+        !self.yes
+    }
+
+    /// Whether the changes should only be shown and not saved.
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Writes the file, relative to the directory of the manifest, once the changes to the
+    /// manifest are saved, so that declining them leaves the package untouched.
+    pub(crate) fn write_after_save(&self, path: impl Into<PathBuf>, contents: String) {
+        let path = self.manifest_dir.join(path.into());
+        let mut pending_writes = self.pending_writes.borrow_mut();
+        pending_writes.retain(|(pending, _)| *pending != path);
+        pending_writes.push((path, contents));
+    }
+
+    /// Writes the files waiting for the manifest to be saved.
+    pub(crate) fn write_pending_files(&self) -> Result<()> {
+        for (path, contents) in self.pending_writes.take() {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+            }
+            std::fs::write(&path, contents).map_err(|e| Error::io(&path, e))?;
+            println!("Wrote {}.", self.display_path(&path));
+        }
+        Ok(())
+    }
+
+    /// Drops the files waiting for the manifest to be saved, as it wasn't.
+    pub(crate) fn discard_pending_files(&self) {
+        for (path, _) in self.pending_writes.take() {
+            println!(
+                "Didn't write {} as the changes weren't saved.",
+                self.display_path(&path)
+            );
+        }
+    }
+
+    /// Shows the path relative to the directory of the manifest, if it is in it.
+    fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.manifest_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+
+    /// Asks a yes/no question unless `--yes` was given.
+    pub(crate) fn confirm(&self, msg: &str) -> Result<bool> {
+        if self.yes {
+            return Ok(true);
+        }
+        Ok(prompt(msg)?)
+    }
+}
+
+/// Converts the value of a field to the answer that fills it with the same value.
+fn value_answer(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s,
+        toml::Value::Array(values) => values
+            .into_iter()
+            .map(value_answer)
+            .collect::<Vec<_>>()
+            .join(", "),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Marks that it was run by setting the description.
+    fn mark(package: &mut Package, _ctx: &Context) -> Result<()> {
+        package.description = Some(cargo_toml::Inheritable::Set("filled".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn fields_missing_from_the_manifest_are_filled() {
+        let file = ManifestFile::parse(
+            PathBuf::from("Cargo.toml"),
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nlicense = \"MIT\"\n".to_string(),
+        )
+        .unwrap();
+        let ctx = Context::new(Answers::default(), Config::default(), true, false, false);
+        let filled = |field| {
+            let mut package = file.package().unwrap();
+            ctx.fill(&file, &mut package, field, mark).unwrap();
+            package.description.is_some()
+        };
+        // `Package` defaults `edition` to 2015, but the manifest doesn't set it.
+        assert!(filled("edition"));
+        assert!(!filled("license"));
+    }
+
+    #[test]
+    fn files_are_written_only_after_the_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Context::new(Answers::default(), Config::default(), true, false, false)
+            .for_manifest(&dir.path().join("Cargo.toml"))
+            .unwrap();
+        ctx.write_after_save("LICENSES/MIT.txt", "MIT".to_string());
+        assert!(!dir.path().join("LICENSES").exists());
+        ctx.discard_pending_files();
+        ctx.write_pending_files().unwrap();
+        assert!(!dir.path().join("LICENSES").exists());
+
+        ctx.write_after_save("LICENSES/MIT.txt", "old".to_string());
+        ctx.write_after_save("LICENSES/MIT.txt", "MIT".to_string());
+        ctx.write_pending_files().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("LICENSES/MIT.txt")).unwrap(),
+            "MIT"
+        );
+    }
+}
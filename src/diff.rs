@@ -0,0 +1,88 @@
+use std::io::IsTerminal;
+
+use similar::{ChangeTag, TextDiff};
+
+pub(crate) const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+pub(crate) const YELLOW: &str = "\x1b[33m";
+pub(crate) const BLUE: &str = "\x1b[34m";
+const CYAN: &str = "\x1b[36m";
+pub(crate) const BOLD: &str = "\x1b[1m";
+pub(crate) const RESET: &str = "\x1b[0m";
+
+/// Whether the output should be colored.
+///
+/// Respects the `NO_COLOR` convention <https://no-color.org>.
+pub(crate) fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Renders a line-level unified diff between the old and the new contents of a file.
+///
+/// Returns `None` if the contents are identical.
+pub(crate) fn unified_diff(path: &str, old: &str, new: &str) -> Option<String> {
+    render_diff(path, old, new, use_color())
+}
+
+fn render_diff(path: &str, old: &str, new: &str, color: bool) -> Option<String> {
+    if old == new {
+        return None;
+    }
+    let (red, green, cyan, bold, reset) = if color {
+        (RED, GREEN, CYAN, BOLD, RESET)
+    } else {
+        ("", "", "", "", "")
+    };
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = format!("{bold}--- {path}\n+++ {path}{reset}\n");
+    for hunk in diff.unified_diff().iter_hunks() {
+        out.push_str(&format!("{cyan}{}{reset}\n", hunk.header()));
+        for change in hunk.iter_changes() {
+            let (sign, color) = match change.tag() {
+                ChangeTag::Delete => ('-', red),
+                ChangeTag::Insert => ('+', green),
+                ChangeTag::Equal => (' ', ""),
+            };
+            let reset = if color.is_empty() { "" } else { reset };
+            out.push_str(&format!("{color}{sign}{change}{reset}"));
+            if change.missing_newline() {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_lines_are_diffed() {
+        let old = "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2018\"\n";
+        let new =
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2021\"\nlicense = \"MIT\"\n";
+        assert_eq!(
+            render_diff("Cargo.toml", old, new, false).unwrap(),
+            r#"--- Cargo.toml
++++ Cargo.toml
+@@ -1,4 +1,5 @@
+ [package]
+ name = "a"
+ version = "0.1.0"
+-edition = "2018"
++edition = "2021"
++license = "MIT"
+"#
+        );
+        assert_eq!(render_diff("Cargo.toml", old, old, false), None);
+    }
+
+    #[test]
+    fn colors_are_reset_after_the_changed_lines() {
+        let diff = render_diff("Cargo.toml", "a\nb\n", "a\nc\n", true).unwrap();
+        assert!(diff.contains(&format!("{RED}-b\n{RESET}")));
+        assert!(diff.contains(&format!("{GREEN}+c\n{RESET}")));
+        assert!(diff.contains("\n a\n"));
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use cargo_toml::{Inheritable, Package};
+
+use crate::answers::Answers;
+use crate::config::Config;
+use crate::context::{Context, Filler};
+use crate::error::Result;
+use crate::fill_rust_version::fill_rust_version;
+use crate::manifest::{is_inherited_value, package_field, to_table, ManifestFile};
+use crate::policy::Policy;
+use crate::report::Diagnostic;
+use crate::validate::{Finding, Severity, Target};
+use crate::workspace::Workspace;
+use crate::{fill_authors, fill_edition, fill_keywords, fill_license, fill_repository};
+
+/// The fields that are expected to be the same across the workspace members.
+const DRIFT_FIELDS: &[(&str, Filler)] = &[
+    ("license", fill_license),
+    ("repository", fill_repository),
+    ("authors", fill_authors),
+    ("rust-version", fill_rust_version),
+    ("edition", fill_edition),
+    ("keywords", fill_keywords),
+];
+
+/// A table that holds the value of a field for one or more members.
+struct Location {
+    /// The name of the member or `[workspace.package]`.
+    name: String,
+    manifest_path: PathBuf,
+    table: &'static [&'static str],
+    value: Option<toml::Value>,
+}
+
+fn display(value: &Option<toml::Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "(unset)".to_string(),
+    }
+}
+
+/// Collects the tables that define the field for the members.
+///
+/// The members that inherit the field are represented by `[workspace.package]`.
+fn locations(workspace: &Workspace, members: &[PathBuf], field: &str) -> Result<Vec<Location>> {
+    let template = ManifestFile::read(&workspace.root)?
+        .manifest()?
+        .workspace
+        .and_then(|workspace| workspace.package)
+        .map(|template| to_table(&template))
+        .unwrap_or_default();
+    let mut locations = Vec::new();
+    let mut inherited = false;
+    for member in members {
+        let Some(package) = ManifestFile::read(member)?.manifest()?.package else {
+            continue;
+        };
+        let value = package_field(&package, field);
+        if value.as_ref().is_some_and(is_inherited_value) {
+            inherited = true;
+            continue;
+        }
+        locations.push(Location {
+            name: package.name,
+            manifest_path: member.clone(),
+            table: &["package"],
+            value,
+        });
+    }
+    if inherited {
+        locations.push(Location {
+            name: "[workspace.package]".to_string(),
+            manifest_path: workspace.root.clone(),
+            table: &["workspace", "package"],
+            value: template.get(field).cloned(),
+        });
+    }
+    Ok(locations)
+}
+
+/// Groups the names of the locations by the values of the field.
+fn groups(locations: &[Location]) -> BTreeMap<String, Vec<&str>> {
+    let mut groups: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for location in locations {
+        groups
+            .entry(display(&location.value))
+            .or_default()
+            .push(&location.name);
+    }
+    groups
+}
+
+/// Locates the mismatches between the members without prompting.
+///
+/// Every table that defines a mismatched field gets a diagnostic.
+pub(crate) fn diagnostics(workspace: &Workspace, members: &[PathBuf]) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    for (field, _) in DRIFT_FIELDS {
+        let locations = locations(workspace, members, field)?;
+        let groups = groups(&locations);
+        if groups.len() <= 1 {
+            continue;
+        }
+        let values: Vec<String> = groups
+            .iter()
+            .map(|(value, names)| format!("{} ({})", value, names.join(", ")))
+            .collect();
+        let message = format!(
+            "`{}` differs between the members: {}",
+            field,
+            values.join("; ")
+        );
+        for location in &locations {
+            let dir = location
+                .manifest_path
+                .parent()
+                .expect("the manifest path has no parent");
+            let severity = Policy::find(dir)?
+                .severity(field)
+                .unwrap_or(Severity::Error);
+            if severity == Severity::Allow {
+                continue;
+            }
+            let file = ManifestFile::read(&location.manifest_path)?;
+            let finding = Finding::new(field, severity, message.clone()).at(Target::Value);
+            diagnostics.push(Diagnostic::locate(&file, location.table, finding));
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// Reports the fields whose values differ between the members.
+///
+/// The severities are taken from the policy of the workspace root.
+/// Returns `false` if any mismatch is an error.
+pub(crate) fn check(workspace: &Workspace, members: &[PathBuf]) -> Result<bool> {
+    println!(
+        "Checking the metadata of the workspace at {}.\n",
+        workspace.root.display()
+    );
+    let dir = workspace
+        .root
+        .parent()
+        .expect("the manifest path has no parent");
+    let policy = Policy::find(dir)?;
+    let mut consistent = true;
+    let mut passed = true;
+    for (field, _) in DRIFT_FIELDS {
+        let severity = policy.severity(field).unwrap_or(Severity::Error);
+        if severity == Severity::Allow {
+            continue;
+        }
+        let locations = locations(workspace, members, field)?;
+        if !report(field, &locations) {
+            consistent = false;
+            passed &= severity != Severity::Error;
+        }
+    }
+    if consistent {
+        println!("The metadata of the members is consistent.");
+    } else {
+        println!("Run `cargo fill unify` to unify the mismatches.");
+    }
+    Ok(passed)
+}
+
+/// Prints the values of the field if they differ between the locations.
+///
+/// Returns `true` if they don't.
+fn report(field: &str, locations: &[Location]) -> bool {
+    let groups = groups(locations);
+    if groups.len() <= 1 {
+        return true;
+    }
+    println!("`{}` differs between the members:", field);
+    for (value, names) in &groups {
+        println!("  {}: {}", value, names.join(", "));
+    }
+    println!();
+    false
+}
+
+/// Reports the fields whose values differ between the members and offers to unify them.
+pub(crate) fn unify(workspace: &Workspace, members: &[PathBuf], dry_run: bool) -> Result<()> {
+    println!(
+        "Unifying the metadata of the workspace at {}.\n",
+        workspace.root.display()
+    );
+    let ctx = Context::new(Answers::default(), Config::load()?, false, true, dry_run)
+        .for_manifest(&workspace.root)?;
+    let mut consistent = true;
+    for (field, filler) in DRIFT_FIELDS {
+        let locations = locations(workspace, members, field)?;
+        if report(field, &locations) {
+            continue;
+        }
+        consistent = false;
+        if ctx.confirm(&format!("Unify `{}`? (Y/n)", field))? {
+            unify_field(&locations, field, *filler, &ctx)?;
+        }
+    }
+    if consistent {
+        println!("The metadata of the members is consistent.");
+    }
+    Ok(())
+}
+
+/// Fills the field once and writes the value to every location.
+///
+/// Nothing is written if the filler was skipped.
+fn unify_field(locations: &[Location], field: &str, filler: Filler, ctx: &Context) -> Result<()> {
+    let mut package = Package::new("workspace", "0.0.0");
+    // `edition` always has a value, so it is made distinguishable from any edition chosen.
+    package.edition = Inheritable::Inherited;
+    let before = package_field(&package, field);
+    ctx.fill_field(&mut package, field, None, filler)?;
+    let value = package_field(&package, field);
+    let Some(value) = value.filter(|value| Some(value) != before.as_ref()) else {
+        return Ok(());
+    };
+    for location in locations {
+        if location.value.as_ref() == Some(&value) {
+            continue;
+        }
+        let mut file = ManifestFile::read(&location.manifest_path)?;
+        let old = location
+            .value
+            .iter()
+            .map(|old| (field.to_string(), old.clone()))
+            .collect();
+        let new = [(field.to_string(), value.clone())].into_iter().collect();
+        file.update(location.table, &old, &new)?;
+        crate::review_and_save(&file, ctx)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::answers::Answer;
+
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    /// A workspace where `a` and `b` inherit the license and `c` has its own
+    /// license and edition.
+    fn workspace(dir: &Path) -> Workspace {
+        // The policy files are searched up to the root of the git repository.
+        std::fs::create_dir(dir.join(".git")).unwrap();
+        write(
+            &dir.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n\n[workspace.package]\nlicense = \"MIT\"\n",
+        );
+        for (name, license, edition) in [
+            ("a", "license.workspace = true", "2021"),
+            ("b", "license.workspace = true", "2021"),
+            ("c", "license = \"Apache-2.0\"", "2018"),
+        ] {
+            write(
+                &dir.join(name).join("Cargo.toml"),
+                &format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"{}\"\n{}\n",
+                    name, edition, license
+                ),
+            );
+        }
+        Workspace::find_required(&dir.join("Cargo.toml")).unwrap()
+    }
+
+    /// The values of the field and the names of the locations with them.
+    fn values(workspace: &Workspace, members: &[PathBuf], field: &str) -> Vec<(String, String)> {
+        let locations = locations(workspace, members, field).unwrap();
+        groups(&locations)
+            .into_iter()
+            .map(|(value, names)| (value, names.join(", ")))
+            .collect()
+    }
+
+    fn pair(value: &str, names: &str) -> (String, String) {
+        (value.to_string(), names.to_string())
+    }
+
+    #[test]
+    fn mismatches_are_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = workspace(dir.path());
+        let members = &workspace.members;
+        assert_eq!(
+            values(&workspace, members, "edition"),
+            [pair("\"2018\"", "c"), pair("\"2021\"", "a, b")]
+        );
+        // The members that inherit the field are represented by `[workspace.package]`.
+        assert_eq!(
+            values(&workspace, members, "license"),
+            [
+                pair("\"Apache-2.0\"", "c"),
+                pair("\"MIT\"", "[workspace.package]")
+            ]
+        );
+        assert_eq!(
+            values(&workspace, &members[..2], "license"),
+            [pair("\"MIT\"", "[workspace.package]")]
+        );
+        assert_eq!(
+            values(&workspace, members, "repository"),
+            [pair("(unset)", "a, b, c")]
+        );
+
+        let fields = |diagnostics: Vec<Diagnostic>| -> Vec<(String, Severity)> {
+            diagnostics
+                .iter()
+                .map(|d| (d.finding.field.clone(), d.finding.severity))
+                .collect()
+        };
+        let error = |field: &str| (field.to_string(), Severity::Error);
+        assert_eq!(
+            fields(diagnostics(&workspace, members).unwrap()),
+            [
+                error("license"),
+                error("license"),
+                error("edition"),
+                error("edition"),
+                error("edition"),
+            ]
+        );
+        assert!(diagnostics(&workspace, &members[..2]).unwrap().is_empty());
+
+        write(
+            &dir.path().join(".cargo-fill.toml"),
+            "[severity]\nlicense = \"allow\"\nedition = \"warning\"\n",
+        );
+        assert!(fields(diagnostics(&workspace, members).unwrap())
+            .iter()
+            .all(|(field, severity)| field == "edition" && *severity == Severity::Warning));
+        assert!(check(&workspace, members).unwrap());
+    }
+
+    #[test]
+    fn fields_are_unified() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = workspace(dir.path());
+        let members = &workspace.members;
+        let mut answers = Answers::default();
+        answers.insert("edition", Answer::Value("2021".to_string()));
+        answers.insert("license", Answer::Value("MIT".to_string()));
+        let ctx = Context::new(answers, Config::default(), true, false, false)
+            .for_manifest(&workspace.root)
+            .unwrap();
+
+        for (field, filler) in DRIFT_FIELDS {
+            let locations = locations(&workspace, members, field).unwrap();
+            if groups(&locations).len() > 1 {
+                unify_field(&locations, field, *filler, &ctx).unwrap();
+            }
+        }
+        assert!(diagnostics(&workspace, members).unwrap().is_empty());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("c/Cargo.toml")).unwrap(),
+            "[package]\nname = \"c\"\nversion = \"0.1.0\"\nedition = \"2021\"\nlicense = \"MIT\"\n"
+        );
+        // The inherited values are already the same.
+        assert!(std::fs::read_to_string(dir.path().join("a/Cargo.toml"))
+            .unwrap()
+            .contains("license.workspace = true"));
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use promptly::ReadlineError;
+
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The errors that stop `cargo fill`.
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// The user aborted a prompt, e.g. with Ctrl-C or Ctrl-D.
+    Aborted,
+    /// Reading the answer to a prompt failed.
+    Prompt(ReadlineError),
+    /// Reading or writing a file failed.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A manifest can't be parsed or doesn't have the expected structure.
+    InvalidManifest { path: PathBuf, message: String },
+    /// An external tool, e.g. `git` or `cargo-msrv`, is unavailable or failed.
+    Tool { command: String, message: String },
+    /// The arguments or the answers are invalid.
+    Usage(String),
+    /// The manifest violates the policy in `.cargo-fill.toml`.
+    Policy(String),
+}
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn invalid_manifest(path: impl Into<PathBuf>, message: impl fmt::Display) -> Self {
+        Error::InvalidManifest {
+            path: path.into(),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn tool(command: impl Into<String>, message: impl fmt::Display) -> Self {
+        Error::Tool {
+            command: command.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// The exit code of the process when the error stops it.
+    ///
+    /// `1` is reserved for failed checks and `2` for invalid command-line arguments.
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => 2,
+            Error::InvalidManifest { .. } => 3,
+            Error::Io { .. } | Error::Prompt(_) => 4,
+            Error::Tool { .. } => 5,
+            Error::Policy(_) => 6,
+            // 128 + SIGINT, like shells do for Ctrl-C.
+            Error::Aborted => 130,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Aborted => f.write_str("aborted by the user"),
+            Error::Prompt(e) => write!(f, "failed to read the answer: {}", e),
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::InvalidManifest { path, message } => {
+                write!(f, "invalid manifest {}: {}", path.display(), message)
+            }
+            Error::Tool { command, message } => write!(f, "`{}` failed: {}", command, message),
+            Error::Usage(message) => f.write_str(message),
+            Error::Policy(message) => write!(f, "policy violation: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Prompt(e) => Some(e),
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<ReadlineError> for Error {
+    fn from(e: ReadlineError) -> Self {
+        match e {
+            ReadlineError::Interrupted | ReadlineError::Eof => Error::Aborted,
+            e => Error::Prompt(e),
+        }
+    }
+}
@@ -1,9 +1,13 @@
 use std::path::PathBuf;
 
 use cargo_toml::{Inheritable, OptionalFile, Package, Publish, Resolver};
-use promptly::{prompt, ReadlineError};
+use promptly::prompt;
 
-fn fill_workplace(package: &mut Package) -> Result<(), ReadlineError> {
+use crate::context::Context;
+use crate::error::Result;
+use crate::manifest::ManifestFile;
+
+fn fill_workplace(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `workspace` field.");
     println!("Description: \"Path to the workspace for the package.\"");
     let workspace: String = loop {
@@ -29,7 +33,7 @@ fn fill_workplace(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_build(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_build(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `build` field.");
     println!("Description: \"Path to the package build script.\"");
     let build = loop {
@@ -55,7 +59,7 @@ fn fill_build(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_links(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_links(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `links` field.");
     println!("Description: \"Name of the native library the package links with.\"");
     let links = loop {
@@ -80,7 +84,7 @@ fn fill_links(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_exclude(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_exclude(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `exclude` field.");
     println!("Description: \"Files to exclude when publishing.\"");
     let exclude = loop {
@@ -110,7 +114,7 @@ fn fill_exclude(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_include(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_include(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `include` field.");
     println!("Description: \"Files to include when publishing.\"");
     let include = loop {
@@ -140,7 +144,11 @@ fn fill_include(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_publish(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_publish(package: &mut Package, ctx: &Context) -> Result<()> {
+    if ctx.policy().publish().is_some() {
+        // The registries are fixed by the policy.
+        return Ok(());
+    }
     println!("Filling the `publish` field.");
     println!("Description: \"Can be used to prevent publishing the package.\"");
 
@@ -175,7 +183,7 @@ fn fill_publish(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_default_run(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_default_run(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `default-run` field.");
     println!("Description: \"The default binary to run by `cargo run`.\"");
 
@@ -201,7 +209,7 @@ fn fill_default_run(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_autobins(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_autobins(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `autobins` field.");
     println!("Description: \"Disables binary auto discovery.\"");
 
@@ -226,7 +234,7 @@ fn fill_autobins(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_autoexamples(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_autoexamples(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `autoexamples` field.");
     println!("Description: \"Disables example auto discovery.\"");
 
@@ -251,7 +259,7 @@ fn fill_autoexamples(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_autotests(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_autotests(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `autotests` field.");
     println!("Description: \"Disables test auto discovery.\"");
 
@@ -276,7 +284,7 @@ fn fill_autotests(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_autobenches(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_autobenches(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `autobenches` field.");
     println!("Description: \"Disables bench auto discovery.\"");
 
@@ -302,7 +310,7 @@ fn fill_autobenches(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-fn fill_resolver(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_resolver(package: &mut Package, _ctx: &Context) -> Result<()> {
     println!("Filling the `resolver` field.");
     println!("Description: \"Sets the dependency resolver to use.\"");
 
@@ -331,8 +339,15 @@ fn fill_resolver(package: &mut Package) -> Result<(), ReadlineError> {
     Ok(())
 }
 
-pub(crate) fn fill_miscellaneous(package: &mut Package) -> Result<(), ReadlineError> {
+pub(crate) fn fill_miscellaneous(
+    file: &ManifestFile,
+    package: &mut Package,
+    ctx: &Context,
+) -> Result<()> {
     // metadata is absent because it is meant to be used and filled by external tools
+    if !ctx.is_interactive() {
+        return Ok(());
+    }
     println!(
         "Filling the miscellaneous fields, namely\n\
     \n\
@@ -368,18 +383,18 @@ pub(crate) fn fill_miscellaneous(package: &mut Package) -> Result<(), ReadlineEr
         break;
     }
 
-    fill_workplace(package)?;
-    fill_build(package)?;
-    fill_links(package)?;
-    fill_exclude(package)?;
-    fill_include(package)?;
-    fill_publish(package)?;
-    fill_default_run(package)?;
-    fill_autobins(package)?;
-    fill_autoexamples(package)?;
-    fill_autotests(package)?;
-    fill_autobenches(package)?;
-    fill_resolver(package)?;
+    ctx.fill(file, package, "workspace", fill_workplace)?;
+    ctx.fill(file, package, "build", fill_build)?;
+    ctx.fill(file, package, "links", fill_links)?;
+    ctx.fill(file, package, "exclude", fill_exclude)?;
+    ctx.fill(file, package, "include", fill_include)?;
+    ctx.fill(file, package, "publish", fill_publish)?;
+    ctx.fill(file, package, "default-run", fill_default_run)?;
+    ctx.fill(file, package, "autobins", fill_autobins)?;
+    ctx.fill(file, package, "autoexamples", fill_autoexamples)?;
+    ctx.fill(file, package, "autotests", fill_autotests)?;
+    ctx.fill(file, package, "autobenches", fill_autobenches)?;
+    ctx.fill(file, package, "resolver", fill_resolver)?;
 
     Ok(())
 }
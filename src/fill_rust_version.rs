@@ -1,16 +1,15 @@
+use std::path::Path;
 use std::process::{Command, Output, Stdio};
 
 use cargo_toml::{Inheritable, Package};
 use crossbeam::thread::scope;
-use promptly::{prompt, ReadlineError};
+use promptly::prompt;
 
-const PROMPT: &'static str = r#"
-Please choose the method of entering the `rust-version` field:
-
-1. Skip (default).
-2. Run `cargo-msrv` <https://github.com/foresterre/cargo-msrv> and fill the field with the result.
-3. Enter the version manually (e.g. `1.54.0`).
-"#;
+use crate::answers::Answer;
+use crate::context::Context;
+use crate::error::{Error, Result};
+use crate::menu::{Choice, Menu};
+use crate::validate::validate_rust_version;
 
 struct TeeWriter<'a, W0: std::io::Write, W1: std::io::Write> {
     w0: &'a mut W0,
@@ -39,18 +38,19 @@ impl<'a, W0: std::io::Write, W1: std::io::Write> std::io::Write for TeeWriter<'a
     }
 }
 
-fn run_msrv(package: &mut Package) -> Result<(), ()> {
+fn run_msrv(package: &mut Package, dir: &Path) -> Result<()> {
     let mut command = Command::new("cargo");
     // Based on question "Capture and inherit stdout and stderr using std::process::Command" on SO
     // Source: https://stackoverflow.com/questions/71141122/capture-and-inherit-stdout-and-stderr-using-stdprocesscommand
     command
         .arg("msrv")
+        .current_dir(dir)
         .stderr(Stdio::piped())
         .stdout(Stdio::piped());
 
     let mut child = command
         .spawn()
-        .unwrap_or_else(|e| panic!("failed to spawn cargo-msrv: {}", e));
+        .map_err(|e| Error::tool("cargo msrv", format!("failed to spawn: {}", e)))?;
     // These expects should be guaranteed to be ok because we used piped().
     let mut child_stdout = child.stdout.take().expect("logic error getting stdout");
     let mut child_stderr = child.stderr.take().expect("logic error getting stderr");
@@ -59,7 +59,7 @@ fn run_msrv(package: &mut Package) -> Result<(), ()> {
         status,
         stdout,
         stderr,
-    } = scope(|s| {
+    } = scope(|s| -> Result<Output> {
         let stdout_thread = s.spawn(|_| -> std::io::Result<Vec<u8>> {
             let stdout = std::io::stdout();
             let mut stdout = stdout.lock();
@@ -80,73 +80,119 @@ fn run_msrv(package: &mut Package) -> Result<(), ()> {
 
         let status = child
             .wait()
-            .unwrap_or_else(|e| panic!("child wasn't running: {}", e));
+            .map_err(|e| Error::tool("cargo msrv", format!("the process wasn't running: {}", e)))?;
 
         let stdout_log = stdout_thread
             .join()
             .unwrap_or_else(|e| panic!("stdout thread panicked: {:?}", e))
-            .unwrap_or_else(|e| panic!("stdout thread failed: {:?}", e));
+            .map_err(|e| Error::tool("cargo msrv", format!("failed to read stdout: {}", e)))?;
         let stderr_log = stderr_thread
             .join()
             .unwrap_or_else(|e| panic!("stderr thread panicked: {:?}", e))
-            .unwrap_or_else(|e| panic!("stderr thread failed: {:?}", e));
+            .map_err(|e| Error::tool("cargo msrv", format!("failed to read stderr: {}", e)))?;
 
-        Output {
+        Ok(Output {
             status,
             stdout: stdout_log,
             stderr: stderr_log,
-        }
+        })
     })
-    .unwrap_or_else(|e| panic!("stdout/stderr thread panicked: {:?}", e));
+    .unwrap_or_else(|e| panic!("stdout/stderr thread panicked: {:?}", e))?;
 
     if !status.success() {
-        eprintln!("cargo-msrv failed with status: {}", status);
         eprintln!("stderr: {}", String::from_utf8_lossy(&stderr));
         eprintln!("stdout: {}", String::from_utf8_lossy(&stdout));
-        return Err(());
+        return Err(Error::tool(
+            "cargo msrv",
+            format!("exited with status: {}", status),
+        ));
     };
     let stderr = String::from_utf8_lossy(&stderr);
-    let msrv = {
-        // E.g. "Check for toolchain '1.61.0-x86_64-pc-windows-msvc' succeeded"
-        let msg = stderr
-            .lines()
-            .rev()
-            .find(|s| s.ends_with("succeeded"))
-            .unwrap();
-        let (msrv, _suffix) = msg
-            .strip_prefix("Check for toolchain '")
-            .unwrap()
-            .split_once('-')
-            .unwrap();
-        msrv
-    };
+    // E.g. "Check for toolchain '1.61.0-x86_64-pc-windows-msvc' succeeded"
+    let msrv = stderr
+        .lines()
+        .rev()
+        .find(|s| s.ends_with("succeeded"))
+        .and_then(|msg| msg.strip_prefix("Check for toolchain '"))
+        .and_then(|msg| msg.split_once('-'))
+        .map(|(msrv, _suffix)| msrv)
+        .ok_or_else(|| Error::tool("cargo msrv", "couldn't find the MSRV in the output"))?;
     package.rust_version = Some(Inheritable::Set(msrv.to_string()));
     Ok(())
 }
 
-pub(crate) fn fill_rust_version(package: &mut Package) -> Result<(), ReadlineError> {
+fn version_from_answer(answer: &Answer) -> Result<String> {
+    let version = answer.value("rust-version")?.to_string();
+    validate_rust_version(&version).map_err(Error::Usage)?;
+    Ok(version)
+}
+
+pub(crate) fn fill_rust_version(package: &mut Package, ctx: &Context) -> Result<()> {
+    match ctx.answer("rust-version") {
+        Some(Answer::Msrv) => match run_msrv(package, ctx.manifest_dir()) {
+            Ok(()) => return Ok(()),
+            Err(e) if ctx.is_interactive() => {
+                eprintln!("error: {}", e);
+                println!("Falling back to entering the version manually.");
+            }
+            Err(e) => return Err(e),
+        },
+        Some(answer) => match version_from_answer(answer) {
+            Ok(version) => {
+                package.rust_version = Some(Inheritable::Set(version));
+                return Ok(());
+            }
+            Err(e) if ctx.is_interactive() => {
+                eprintln!("error: {}", e);
+                println!("Falling back to entering the version manually.");
+            }
+            Err(e) => return Err(e),
+        },
+        None if !ctx.is_interactive() => return Ok(()),
+        None => {}
+    }
     println!("Filling the `rust-version` field.");
     println!("Description: \"The minimal supported Rust version.\"");
+    let menu = Menu::new("Please choose the method of entering the `rust-version` field:")
+        .option("Skip.", Choice::Answer(Answer::Skip))
+        .option(
+            "Run `cargo-msrv` <https://github.com/foresterre/cargo-msrv> and fill the field with the result.",
+            Choice::Answer(Answer::Msrv),
+        )
+        .option(
+            "Enter the version manually (e.g. `1.54.0`).",
+            Choice::Manual,
+        )
+        .with_default(ctx.field_default("rust-version", &package.name))
+        .required(ctx.policy().is_required("rust-version"));
     loop {
-        let c: String = prompt(PROMPT)?;
-        match c.as_str() {
-            "1" => {
-                // skip
-                break;
-            }
-            "2" => {
-                if let Err(_) = run_msrv(package) {
+        match menu.choose()? {
+            Choice::Answer(Answer::Skip) => break,
+            Choice::Answer(Answer::Msrv) => {
+                if let Err(e) = run_msrv(package, ctx.manifest_dir()) {
+                    eprintln!("error: {}", e);
                     continue;
                 }
+                ctx.used_strategy(Answer::Msrv);
                 break;
             }
-            "3" => {
+            Choice::Answer(answer) => match version_from_answer(&answer) {
+                Ok(version) => {
+                    package.rust_version = Some(Inheritable::Set(version));
+                    break;
+                }
+                Err(e) => println!("{}", e),
+            },
+            Choice::Manual => {
                 let version: String = prompt("Please enter the version, e.g. `1.54.0`")?;
+                if let Err(e) = validate_rust_version(&version) {
+                    println!("{}", e);
+                    continue;
+                }
                 package.rust_version = Some(Inheritable::Set(version));
                 break;
             }
-            _ => println!("{}", PROMPT),
-        };
+        }
     }
     println!();
     Ok(())
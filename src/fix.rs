@@ -0,0 +1,305 @@
+use std::path::Path;
+
+use cargo_toml::{Inheritable, Package, Publish};
+
+use crate::answers::{Answer, Answers};
+use crate::config::Config;
+use crate::context::{Context, Filler};
+use crate::error::Result;
+use crate::manifest::{package_field, ManifestFile};
+use crate::{
+    fill_authors, fill_documentation, fill_license_file, fill_readme, fill_repository, git_author,
+    git_repository, review_and_save,
+};
+
+/// Decides how to fill a missing field from the directory of the package.
+type Decide = fn(&Package, &Path, &Context) -> Decision;
+
+/// The fields that `cargo fill fix` can derive, in the order they are filled.
+const FIX_FIELDS: &[(&str, Filler, Decide)] = &[
+    ("authors", fill_authors, decide_authors),
+    ("documentation", fill_documentation, decide_documentation),
+    ("readme", fill_readme, decide_readme),
+    ("repository", fill_repository, decide_repository),
+    ("license-file", fill_license_file, decide_license_file),
+];
+
+/// The README files that cargo recognizes, in the order of preference.
+const README_FILES: &[&str] = &["README.md", "README.txt", "README"];
+
+/// The files that hold the text of a single license.
+const LICENSE_FILES: &[&str] = &[
+    "LICENSE",
+    "LICENSE.md",
+    "LICENSE.txt",
+    "COPYING",
+    "COPYING.md",
+    "COPYING.txt",
+];
+
+/// What `cargo fill fix` decided for a missing field.
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    /// Fill the field with the answer, for the reason.
+    Fill(Answer, String),
+    /// Leave the field alone, for the reason.
+    Leave(String),
+}
+
+/// Decides how to fill the missing field of the package without asking.
+///
+/// Only the values that can be derived with high confidence are used.
+fn decide(package: &Package, field: &str, decide_field: Decide, ctx: &Context) -> Decision {
+    match ctx.policy().fixed(field) {
+        Some(answer) => Decision::Fill(answer.clone(), "fixed by the policy".to_string()),
+        None => decide_field(package, ctx.manifest_dir(), ctx),
+    }
+}
+
+fn decide_authors(_package: &Package, dir: &Path, _ctx: &Context) -> Decision {
+    match git_author(dir) {
+        Ok(_) => Decision::Fill(Answer::FromGit, "from git config".to_string()),
+        Err(e) => Decision::Leave(e.to_string()),
+    }
+}
+
+fn decide_documentation(package: &Package, dir: &Path, _ctx: &Context) -> Decision {
+    if !dir.join("src").join("lib.rs").is_file() {
+        Decision::Leave("not a library crate".to_string())
+    } else if let Inheritable::Set(Publish::Flag(false)) = package.publish {
+        Decision::Leave("the package is not published to docs.rs".to_string())
+    } else {
+        Decision::Fill(Answer::DocsRs, "a library crate".to_string())
+    }
+}
+
+fn decide_readme(_package: &Package, dir: &Path, _ctx: &Context) -> Decision {
+    match README_FILES.iter().find(|file| dir.join(file).is_file()) {
+        Some(file) => Decision::Fill(Answer::Value(file.to_string()), format!("found {}", file)),
+        None => Decision::Leave("no README file found".to_string()),
+    }
+}
+
+fn decide_repository(_package: &Package, dir: &Path, ctx: &Context) -> Decision {
+    let repository = match git_repository(dir) {
+        Ok(repository) => repository,
+        Err(e) => return Decision::Leave(e.to_string()),
+    };
+    if !repository.starts_with("https://") {
+        return Decision::Leave(format!("the git remote `{}` is not a URL", repository));
+    }
+    match ctx.policy().check_repository(&repository) {
+        Ok(()) => Decision::Fill(Answer::FromGit, "from the git remote".to_string()),
+        Err(e) => Decision::Leave(e.to_string()),
+    }
+}
+
+fn decide_license_file(_package: &Package, dir: &Path, _ctx: &Context) -> Decision {
+    let found: Vec<&str> = LICENSE_FILES
+        .iter()
+        .copied()
+        .filter(|file| dir.join(file).is_file())
+        .collect();
+    match found[..] {
+        [file] => Decision::Fill(Answer::Value(file.to_string()), format!("found {}", file)),
+        [] => Decision::Leave("no license file found".to_string()),
+        _ => Decision::Leave(format!("several license files found: {}", found.join(", "))),
+    }
+}
+
+/// Fills the missing fields of the manifest that can be derived without asking
+/// and prints what was done.
+pub(crate) fn fix_manifest(path: &Path, dry_run: bool) -> Result<()> {
+    let ctx = Context::new(Answers::default(), Config::load()?, true, false, dry_run)
+        .for_manifest(path)?;
+    let mut file = ManifestFile::read(path)?;
+    let original = file.package()?;
+    let mut package = original.clone();
+
+    let mut answers = Answers::default();
+    let mut fills = Vec::new();
+    let mut left = Vec::new();
+    for (field, filler, decide_field) in FIX_FIELDS {
+        // The license file is only needed when there is no license expression.
+        let is_needed = *field != "license-file" || package.license.is_none();
+        if package_field(&package, field).is_some() || !is_needed {
+            continue;
+        }
+        match decide(&package, field, *decide_field, &ctx) {
+            Decision::Fill(answer, reason) => {
+                answers.insert(field, answer);
+                fills.push((*field, *filler, reason));
+            }
+            Decision::Leave(reason) => left.push((*field, reason)),
+        }
+    }
+    let ctx = ctx.with_answers(answers);
+    for (field, filler, reason) in fills {
+        ctx.fill(&file, &mut package, field, filler)?;
+        if let Some(value) = package_field(&package, field) {
+            println!("Filled `{}` with {} ({}).", field, value, reason);
+        }
+    }
+    for (field, reason) in left {
+        println!("Left `{}` alone: {}.", field, reason);
+    }
+    println!();
+
+    file.update_package(&original, &package)?;
+    review_and_save(&file, &ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decides the field for a package in the directory, with the policy found there.
+    fn decision(dir: &Path, package: &Package, field: &str) -> Decision {
+        let ctx = Context::new(Answers::default(), Config::default(), true, false, true)
+            .for_manifest(&dir.join("Cargo.toml"))
+            .unwrap();
+        let (_, _, decide_field) = FIX_FIELDS.iter().find(|(name, ..)| *name == field).unwrap();
+        decide(package, field, *decide_field, &ctx)
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn touch(dir: &Path, file: &str) {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+
+    fn fill(answer: Answer, reason: &str) -> Decision {
+        Decision::Fill(answer, reason.to_string())
+    }
+
+    fn leave(reason: &str) -> Decision {
+        Decision::Leave(reason.to_string())
+    }
+
+    #[test]
+    fn git_fields_are_filled_from_the_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let package = Package::new("a", "0.1.0");
+        git(dir, &["init", "-q"]);
+        git(dir, &["config", "user.name", "Jane Doe"]);
+        git(dir, &["config", "user.email", "jane@example.com"]);
+        assert_eq!(
+            decision(dir, &package, "authors"),
+            fill(Answer::FromGit, "from git config")
+        );
+
+        assert!(matches!(
+            decision(dir, &package, "repository"),
+            Decision::Leave(_)
+        ));
+        git(
+            dir,
+            &["remote", "add", "origin", "git@github.com:acme/a.git"],
+        );
+        assert_eq!(
+            decision(dir, &package, "repository"),
+            leave("the git remote `git@github.com:acme/a` is not a URL")
+        );
+        git(
+            dir,
+            &[
+                "remote",
+                "set-url",
+                "origin",
+                "https://github.com/acme/a.git",
+            ],
+        );
+        assert_eq!(
+            decision(dir, &package, "repository"),
+            fill(Answer::FromGit, "from the git remote")
+        );
+        std::fs::write(
+            dir.join(".cargo-fill.toml"),
+            "repository-prefix = \"https://github.com/corp/\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            decision(dir, &package, "repository"),
+            leave(
+                "policy violation: the repository URL must start with `https://github.com/corp/`"
+            )
+        );
+    }
+
+    #[test]
+    fn files_are_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let mut package = Package::new("a", "0.1.0");
+        assert_eq!(
+            decision(dir, &package, "documentation"),
+            leave("not a library crate")
+        );
+        assert_eq!(
+            decision(dir, &package, "readme"),
+            leave("no README file found")
+        );
+        assert_eq!(
+            decision(dir, &package, "license-file"),
+            leave("no license file found")
+        );
+
+        for file in ["src/lib.rs", "README", "README.md", "COPYING"] {
+            touch(dir, file);
+        }
+        assert_eq!(
+            decision(dir, &package, "documentation"),
+            fill(Answer::DocsRs, "a library crate")
+        );
+        assert_eq!(
+            decision(dir, &package, "readme"),
+            fill(Answer::Value("README.md".to_string()), "found README.md")
+        );
+        assert_eq!(
+            decision(dir, &package, "license-file"),
+            fill(Answer::Value("COPYING".to_string()), "found COPYING")
+        );
+
+        package.publish = Inheritable::Set(Publish::Flag(false));
+        assert_eq!(
+            decision(dir, &package, "documentation"),
+            leave("the package is not published to docs.rs")
+        );
+        touch(dir, "LICENSE");
+        assert_eq!(
+            decision(dir, &package, "license-file"),
+            leave("several license files found: LICENSE, COPYING")
+        );
+    }
+
+    #[test]
+    fn fixed_answers_take_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        // The policy files are searched up to the root of the git repository.
+        std::fs::create_dir(dir.join(".git")).unwrap();
+        std::fs::write(
+            dir.join(".cargo-fill.toml"),
+            "[fixed]\nreadme = \"docs/README.md\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            decision(dir, &Package::new("a", "0.1.0"), "readme"),
+            fill(
+                Answer::Value("docs/README.md".to_string()),
+                "fixed by the policy"
+            )
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use std::cmp::Reverse;
+use std::fmt;
+
+use promptly::{prompt, prompt_opt};
+use spdx::error::Reason;
+use spdx::lexer::{Lexer, Token};
+use spdx::{Expression, LicenseId, ParseMode};
+
+use crate::answers::Answer;
+use crate::error::Result;
+use crate::menu::{Choice, Menu};
+
+/// The maximum number of search results shown at once.
+const MAX_RESULTS: usize = 10;
+
+/// An SPDX license expression that can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InvalidLicense {
+    pub(crate) message: String,
+    /// The identifier that was most likely meant, if the expression has an unknown one.
+    pub(crate) suggestion: Option<&'static str>,
+}
+
+impl fmt::Display for InvalidLicense {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(suggestion) = self.suggestion {
+            write!(f, ", did you mean `{}`?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the license or exception identifier that the unknown one is most likely
+/// a misspelling of.
+pub(crate) fn suggest(id: &str) -> Option<&'static str> {
+    let id = id.to_lowercase();
+    let licenses = spdx::identifiers::LICENSES.iter().map(|(name, ..)| *name);
+    let exceptions = spdx::identifiers::EXCEPTIONS.iter().map(|(name, _)| *name);
+    licenses
+        .chain(exceptions)
+        .map(|name| {
+            let similarity = strsim::normalized_damerau_levenshtein(&id, &name.to_lowercase());
+            (similarity, name)
+        })
+        .filter(|(similarity, _)| *similarity >= 0.6)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, name)| name)
+}
+
+/// The deprecated licenses that are replaced by another license or an exception,
+/// as described in the SPDX license list.
+const REPLACEMENTS: &[(&str, &str)] = &[
+    ("BSD-2-Clause-FreeBSD", "BSD-2-Clause-Views"),
+    ("BSD-2-Clause-NetBSD", "BSD-2-Clause"),
+    (
+        "GPL-2.0-with-GCC-exception",
+        "GPL-2.0-only WITH GCC-exception-2.0",
+    ),
+    (
+        "GPL-2.0-with-autoconf-exception",
+        "GPL-2.0-only WITH Autoconf-exception-2.0",
+    ),
+    (
+        "GPL-2.0-with-bison-exception",
+        "GPL-2.0-only WITH Bison-exception-2.2",
+    ),
+    (
+        "GPL-2.0-with-classpath-exception",
+        "GPL-2.0-only WITH Classpath-exception-2.0",
+    ),
+    (
+        "GPL-2.0-with-font-exception",
+        "GPL-2.0-only WITH Font-exception-2.0",
+    ),
+    (
+        "GPL-3.0-with-GCC-exception",
+        "GPL-3.0-only WITH GCC-exception-3.1",
+    ),
+    (
+        "GPL-3.0-with-autoconf-exception",
+        "GPL-3.0-only WITH Autoconf-exception-3.0",
+    ),
+    ("Nunit", "zlib-acknowledgement"),
+    ("StandardML-NJ", "SMLNJ"),
+    ("bzip2-1.0.5", "bzip2-1.0.6"),
+    ("eCos-2.0", "GPL-2.0-or-later WITH eCos-exception-2.0"),
+    (
+        "wxWindows",
+        "LGPL-2.0-or-later WITH WxWindows-exception-3.1",
+    ),
+];
+
+/// Returns the current form of a deprecated license, e.g. `GPL-3.0-only` for `GPL-3.0`
+/// or `SMLNJ` for `StandardML-NJ`, or `None` if it has none.
+fn current_license(id: LicenseId) -> Option<&'static str> {
+    if !id.is_deprecated() {
+        return Some(id.name);
+    }
+    if let Some((_, current)) = REPLACEMENTS.iter().find(|(name, _)| *name == id.name) {
+        return Some(current);
+    }
+    spdx::license_id(&format!("{}-only", id.name)).map(|id| id.name)
+}
+
+/// Parses the SPDX license expression against the bundled SPDX license list.
+///
+/// Returns the expression in its canonical form. Legacy forms are rewritten,
+/// e.g. `MIT/Apache-2.0` to `MIT OR Apache-2.0` and `GPL-3.0` to `GPL-3.0-only`,
+/// and the deprecated identifiers without a current form are rejected.
+pub(crate) fn normalize_license(expression: &str) -> Result<String, InvalidLicense> {
+    let invalid = |span: std::ops::Range<usize>, reason: Reason| {
+        // The lexer may have already consumed a prefix of the term, e.g. `apache` of `apache2.0`.
+        let is_separator = |c: char| c.is_whitespace() || "()/".contains(c);
+        let start = expression[..span.start]
+            .rfind(is_separator)
+            .map_or(0, |i| i + 1);
+        let end = expression[span.start..]
+            .find(is_separator)
+            .map_or(expression.len(), |i| span.start + i);
+        let term = &expression[start..end];
+        match reason {
+            Reason::UnknownTerm | Reason::UnknownLicense => InvalidLicense {
+                message: format!("unknown license `{}`", term),
+                suggestion: suggest(term),
+            },
+            Reason::UnknownException => InvalidLicense {
+                message: format!("unknown license exception `{}`", term),
+                suggestion: suggest(term),
+            },
+            reason => InvalidLicense {
+                message: format!("invalid license expression `{}`: {}", expression, reason),
+                suggestion: None,
+            },
+        }
+    };
+
+    let mut normalized = String::new();
+    // The start of the last GNU license in the normalized expression, for the `+` suffix.
+    let mut last_gnu = None;
+    for token in Lexer::new_mode(expression, ParseMode::LAX) {
+        let token = token.map_err(|e| invalid(e.span, e.reason))?;
+        match token.token {
+            Token::Spdx(id) => {
+                let current = current_license(id).ok_or_else(|| InvalidLicense {
+                    message: format!(
+                        "the license `{}` is deprecated and has no replacement, \
+                         see <https://spdx.org/licenses> for the current identifiers",
+                        id.name
+                    ),
+                    suggestion: None,
+                })?;
+                // Only a plain license can take the `+` suffix.
+                last_gnu = (id.is_gnu() && !current.contains(' ')).then_some(normalized.len());
+                normalized.push_str(current);
+            }
+            Token::Plus => match last_gnu.take() {
+                Some(start) => {
+                    let base = normalized[start..].trim_end_matches("-only").to_string();
+                    normalized.truncate(start);
+                    normalized.push_str(&base);
+                    normalized.push_str("-or-later");
+                }
+                None => normalized.push('+'),
+            },
+            Token::LicenseRef { doc_ref, lic_ref } => {
+                if let Some(doc_ref) = doc_ref {
+                    normalized.push_str(&format!("DocumentRef-{}:", doc_ref));
+                }
+                normalized.push_str(&format!("LicenseRef-{}", lic_ref));
+            }
+            Token::Exception(exception) if exception.is_deprecated() => {
+                return Err(InvalidLicense {
+                    message: format!(
+                        "the license exception `{}` is deprecated, see \
+                         <https://spdx.org/licenses/exceptions-index.html> for the current ones",
+                        exception.name
+                    ),
+                    suggestion: None,
+                });
+            }
+            Token::Exception(exception) => normalized.push_str(exception.name),
+            Token::OpenParen => normalized.push('('),
+            Token::CloseParen => normalized.push(')'),
+            Token::With => normalized.push_str(" WITH "),
+            Token::And => normalized.push_str(" AND "),
+            Token::Or => normalized.push_str(" OR "),
+        }
+    }
+    // The structure, e.g. the balance of the parentheses, is checked on the normalized expression.
+    Expression::parse(&normalized).map_err(|e| InvalidLicense {
+        message: format!("invalid license expression `{}`: {}", expression, e.reason),
+        suggestion: None,
+    })?;
+    Ok(normalized)
+}
+
+/// The most license terms that [`is_same_license`] compares the expressions for.
+const MAX_COMPARED_TERMS: usize = 12;
+
+/// Whether the license expressions are satisfied by the same licenses,
+/// e.g. `MIT OR Apache-2.0` and `Apache-2.0 OR MIT`.
+pub(crate) fn is_same_license(a: &str, b: &str) -> bool {
+    let normalize =
+        |expression| normalize_license(expression).unwrap_or_else(|_| expression.to_string());
+    let (a, b) = (normalize(a), normalize(b));
+    let (Ok(first), Ok(second)) = (Expression::parse(&a), Expression::parse(&b)) else {
+        return a == b;
+    };
+    let mut terms: Vec<String> = first
+        .requirements()
+        .chain(second.requirements())
+        .map(|requirement| requirement.req.to_string())
+        .collect();
+    terms.sort();
+    terms.dedup();
+    if terms.len() > MAX_COMPARED_TERMS {
+        return a == b;
+    }
+    // The expressions are compared for every combination of the accepted terms.
+    (0..1_u32 << terms.len()).all(|accepted| {
+        let is_accepted = |req: &spdx::LicenseReq| {
+            terms
+                .iter()
+                .position(|term| *term == req.to_string())
+                .is_some_and(|i| accepted & (1 << i) != 0)
+        };
+        first.evaluate(is_accepted) == second.evaluate(is_accepted)
+    })
+}
+
+/// Searches the bundled SPDX license list, ignoring the deprecated licenses.
+///
+/// The licenses whose identifier matches the query come first, then the ones whose
+/// full name does, then the ones whose identifier contains the characters of the query in order.
+/// The OSI-approved licenses come first among equal matches.
+pub(crate) fn search_licenses(query: &str) -> Vec<LicenseId> {
+    let query = query.trim().to_lowercase();
+    let is_subsequence = |s: &str| {
+        let mut chars = s.chars();
+        query.chars().all(|q| chars.any(|c| c == q))
+    };
+    let mut results: Vec<(u8, LicenseId)> = spdx::identifiers::LICENSES
+        .iter()
+        .filter_map(|(name, ..)| spdx::license_id(name))
+        .filter(|id| !id.is_deprecated())
+        .filter_map(|id| {
+            let name = id.name.to_lowercase();
+            let full_name = id.full_name.to_lowercase();
+            let rank = if name == query {
+                0
+            } else if name.starts_with(&query) {
+                1
+            } else if name.contains(&query) {
+                2
+            } else if full_name.contains(&query) {
+                3
+            } else if is_subsequence(&name) {
+                4
+            } else {
+                return None;
+            };
+            Some((rank, id))
+        })
+        .collect();
+    // The OSI-approved licenses are the most common ones, and the latest versions come first.
+    results.sort_by_key(|(rank, id)| {
+        let is_osi_approved = id.is_osi_approved();
+        (*rank, !is_osi_approved, id.name.len(), Reverse(id.name))
+    });
+    results.into_iter().map(|(_, id)| id).collect()
+}
+
+/// Describes the license for the picker, e.g. `MIT: MIT License (OSI approved)`.
+fn describe(id: LicenseId) -> String {
+    let mut traits = Vec::new();
+    if id.is_osi_approved() {
+        traits.push("OSI approved");
+    }
+    if id.is_copyleft() {
+        traits.push("copyleft");
+    }
+    if traits.is_empty() {
+        format!("{}: {}", id.name, id.full_name)
+    } else {
+        format!("{}: {} ({})", id.name, id.full_name, traits.join(", "))
+    }
+}
+
+/// Lets the user search the SPDX license list and select one or more licenses.
+///
+/// A full SPDX expression can be entered instead. Several selected licenses are
+/// combined with `OR` or `AND`.
+pub(crate) fn pick_license() -> Result<String> {
+    let mut results = Vec::new();
+    let mut selected: Vec<&'static str> = Vec::new();
+    loop {
+        let msg = if selected.is_empty() {
+            "Please enter a search term, the numbers of the licenses to select or an SPDX expression"
+        } else {
+            "Please enter a search term, the numbers of the licenses to toggle or nothing to finish"
+        };
+        let input: Option<String> = prompt_opt(msg)?;
+        let input = input.as_deref().unwrap_or_default().trim();
+        if input.is_empty() {
+            if selected.is_empty() {
+                continue;
+            }
+            break;
+        }
+        let numbers: Option<Vec<usize>> = input
+            .split([',', ' '])
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().ok())
+            .collect();
+        if let Some(numbers) = numbers {
+            for number in numbers {
+                let Some(id) = number.checked_sub(1).and_then(|i| results.get(i)) else {
+                    println!("There is no license number {}.", number);
+                    continue;
+                };
+                let id: &LicenseId = id;
+                match selected.iter().position(|name| *name == id.name) {
+                    Some(i) => {
+                        selected.remove(i);
+                    }
+                    None => selected.push(id.name),
+                }
+            }
+            println!("Selected: {}.", selected.join(", "));
+            continue;
+        }
+        // A search term is rarely a valid expression with an operator or an exact identifier.
+        let is_expression = [" OR ", " AND ", " WITH ", "("]
+            .iter()
+            .any(|operator| input.contains(operator))
+            || spdx::license_id(input).is_some_and(|id| id.name == input);
+        if is_expression {
+            match normalize_license(input) {
+                Ok(expression) => return Ok(expression),
+                Err(e) => println!("{}", e),
+            }
+            continue;
+        }
+        results = search_licenses(input);
+        if results.is_empty() {
+            println!("No licenses match `{}`.", input);
+            continue;
+        }
+        let total = results.len();
+        results.truncate(MAX_RESULTS);
+        for (i, id) in results.iter().enumerate() {
+            let mark = if selected.contains(&id.name) {
+                "*"
+            } else {
+                " "
+            };
+            println!("{}{}. {}", mark, i + 1, describe(*id));
+        }
+        if total > MAX_RESULTS {
+            println!(
+                "... and {} more, refine the search to see them.",
+                total - MAX_RESULTS
+            );
+        }
+    }
+    if let [license] = selected[..] {
+        return Ok(license.to_string());
+    }
+    let expression = |operator| selected.join(operator);
+    let menu = Menu::new("Please choose how the licenses apply.")
+        .option(
+            format!(
+                "Any of them at the user's option, i.e. `{}`.",
+                expression(" OR ")
+            ),
+            Choice::Answer(Answer::Value(expression(" OR "))),
+        )
+        .option(
+            format!("All of them, i.e. `{}`.", expression(" AND ")),
+            Choice::Answer(Answer::Value(expression(" AND "))),
+        )
+        .option(
+            "Another combination, entered as an SPDX expression.",
+            Choice::Manual,
+        );
+    match menu.choose()? {
+        Choice::Answer(expression) => Ok(expression.to_string()),
+        Choice::Manual => loop {
+            let input: String = prompt("Please enter the SPDX expression")?;
+            match normalize_license(&input) {
+                Ok(expression) => return Ok(expression),
+                Err(e) => println!("{}", e),
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn licenses_are_normalized() {
+        let normalized = |expression| normalize_license(expression).unwrap();
+        assert_eq!(normalized("MIT OR Apache-2.0"), "MIT OR Apache-2.0");
+        assert_eq!(normalized("MIT/Apache-2.0"), "MIT OR Apache-2.0");
+        assert_eq!(normalized("GPL-3.0"), "GPL-3.0-only");
+        assert_eq!(normalized("GPL-2.0+"), "GPL-2.0-or-later");
+        assert_eq!(
+            normalized("GPL-2.0-with-classpath-exception"),
+            "GPL-2.0-only WITH Classpath-exception-2.0"
+        );
+        assert_eq!(normalized("StandardML-NJ OR MIT"), "SMLNJ OR MIT");
+        assert_eq!(
+            normalized("wxWindows"),
+            "LGPL-2.0-or-later WITH WxWindows-exception-3.1"
+        );
+        // Every replacement is a valid expression of current identifiers.
+        for (deprecated, current) in REPLACEMENTS {
+            assert_eq!(normalized(deprecated), *current);
+        }
+        assert!(normalize_license("Net-SNMP").is_err());
+        assert!(normalize_license("LGPL-2.1 WITH Nokia-Qt-exception-1.1").is_err());
+        assert_eq!(
+            normalized("(MIT or Apache-2.0) AND Apache-2.0 WITH LLVM-exception"),
+            "(MIT OR Apache-2.0) AND Apache-2.0 WITH LLVM-exception"
+        );
+    }
+
+    #[test]
+    fn equivalent_licenses_are_the_same() {
+        assert!(is_same_license("MIT OR Apache-2.0", "Apache-2.0 OR MIT"));
+        assert!(is_same_license("MIT/Apache-2.0", "MIT OR Apache-2.0"));
+        assert!(is_same_license(
+            "MIT AND (Apache-2.0 WITH LLVM-exception OR Zlib)",
+            "(Zlib OR Apache-2.0 WITH LLVM-exception) AND MIT"
+        ));
+        assert!(!is_same_license("MIT OR Apache-2.0", "MIT AND Apache-2.0"));
+        assert!(!is_same_license(
+            "Apache-2.0",
+            "Apache-2.0 WITH LLVM-exception"
+        ));
+    }
+
+    #[test]
+    fn licenses_are_searched() {
+        let names = |query| -> Vec<&str> {
+            search_licenses(query)
+                .iter()
+                .take(3)
+                .map(|id| id.name)
+                .collect()
+        };
+        assert_eq!(names("mit")[..2], ["MIT", "MIT-0"]);
+        assert_eq!(names("apache")[0], "Apache-2.0");
+        // The deprecated identifiers are not offered.
+        assert!(!search_licenses("GPL-3.0")
+            .iter()
+            .any(|id| id.name == "GPL-3.0"));
+        assert!(names("mozilla").contains(&"MPL-2.0"));
+    }
+
+    #[test]
+    fn invalid_licenses_are_rejected() {
+        let error = normalize_license("MIT OR Apache2.0").unwrap_err();
+        assert_eq!(error.message, "unknown license `Apache2.0`");
+        assert_eq!(error.suggestion, Some("Apache-2.0"));
+        assert_eq!(
+            normalize_license("MTI").unwrap_err().suggestion,
+            Some("MIT")
+        );
+        assert!(normalize_license("(MIT OR Apache-2.0").is_err());
+        assert!(normalize_license("MIT AND").is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cargo_toml::{Inheritable, Package};
+use promptly::prompt_default;
+use spdx::Expression;
+
+use crate::context::Context;
+use crate::error::Result;
+use crate::{git_config, reuse};
+
+/// The bundled license and exception texts, with `{year}` and `{holder}` placeholders.
+const TEMPLATES: &[(&str, &str)] = &[
+    ("0BSD", include_str!("../templates/licenses/0BSD.txt")),
+    (
+        "Apache-2.0",
+        include_str!("../templates/licenses/Apache-2.0.txt"),
+    ),
+    (
+        "BSD-2-Clause",
+        include_str!("../templates/licenses/BSD-2-Clause.txt"),
+    ),
+    (
+        "BSD-3-Clause",
+        include_str!("../templates/licenses/BSD-3-Clause.txt"),
+    ),
+    ("ISC", include_str!("../templates/licenses/ISC.txt")),
+    (
+        "LLVM-exception",
+        include_str!("../templates/licenses/LLVM-exception.txt"),
+    ),
+    ("MIT", include_str!("../templates/licenses/MIT.txt")),
+    ("MIT-0", include_str!("../templates/licenses/MIT-0.txt")),
+    ("MPL-2.0", include_str!("../templates/licenses/MPL-2.0.txt")),
+    (
+        "Unlicense",
+        include_str!("../templates/licenses/Unlicense.txt"),
+    ),
+    ("Zlib", include_str!("../templates/licenses/Zlib.txt")),
+];
+
+/// Returns the text of the license with the placeholders filled in,
+/// if the license has a bundled template.
+pub(crate) fn license_text(id: &str, year: i64, holder: &str) -> Option<String> {
+    let (_, template) = TEMPLATES
+        .iter()
+        .find(|(template_id, _)| *template_id == id)?;
+    Some(
+        template
+            .replace("{year}", &year.to_string())
+            .replace("{holder}", holder),
+    )
+}
+
+/// Returns the SPDX identifiers of the licenses in the expression, in order and without duplicates.
+///
+/// The `LicenseRef-` licenses are not included.
+pub(crate) fn license_ids(expression: &str) -> Vec<&'static str> {
+    let Ok(expression) = Expression::parse(expression) else {
+        return Vec::new();
+    };
+    let mut ids = Vec::new();
+    for requirement in expression.requirements() {
+        if let Some(id) = requirement.req.license.id() {
+            if !ids.contains(&id.name) {
+                ids.push(id.name);
+            }
+        }
+    }
+    ids
+}
+
+/// Returns the SPDX identifiers of the exceptions in the `WITH` clauses of the expression,
+/// in order and without duplicates.
+fn exception_ids(expression: &str) -> Vec<&'static str> {
+    let Ok(expression) = Expression::parse(expression) else {
+        return Vec::new();
+    };
+    let mut ids = Vec::new();
+    for requirement in expression.requirements() {
+        if let Some(exception) = requirement.req.exception {
+            if !ids.contains(&exception.name) {
+                ids.push(exception.name);
+            }
+        }
+    }
+    ids
+}
+
+/// Returns the name of the file with the text of the license, following the convention
+/// of the Rust ecosystem, e.g. `LICENSE-MIT` and `LICENSE-APACHE` for a dual license.
+fn file_name(id: &str, is_single: bool) -> String {
+    if is_single {
+        return "LICENSE".to_string();
+    }
+    let suffix = match id {
+        "Apache-2.0" => "APACHE".to_string(),
+        "MPL-2.0" => "MPL".to_string(),
+        id => id.to_uppercase(),
+    };
+    format!("LICENSE-{}", suffix)
+}
+
+/// Returns the current year in UTC.
+pub(crate) fn current_year() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    // The civil-from-days algorithm by Howard Hinnant,
+    // see <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    yoe + era * 400 + i64::from(month <= 2)
+}
+
+/// Returns the copyright holder for the license texts: the names of the authors
+/// without their emails, the git user or the authors of the package in general.
+pub(crate) fn copyright_holder(package: &Package, dir: &Path) -> String {
+    if let Inheritable::Set(authors) = &package.authors {
+        let names: Vec<&str> = authors
+            .iter()
+            .map(|author| author.split(" <").next().unwrap_or(author).trim())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if !names.is_empty() {
+            return names.join(", ");
+        }
+    }
+    git_config("user.name", dir).unwrap_or_else(|_| format!("The {} Authors", package.name))
+}
+
+/// Offers to write the texts of the licenses in the `license` field of the package
+/// from the bundled templates.
+///
+/// The packages that follow the REUSE specification get `LICENSES/<id>.txt` instead,
+/// including the texts of the exceptions.
+///
+/// Since the license texts are only needed in `license-file` for the licenses
+/// that are not in the SPDX list, `license-file` is removed once they are written.
+pub(crate) fn offer_license_files(package: &mut Package, ctx: &Context) -> Result<()> {
+    let Some(Inheritable::Set(license)) = &package.license else {
+        return Ok(());
+    };
+    let ids = license_ids(license);
+    let dir = ctx.manifest_dir();
+    let is_reuse = reuse::is_enabled(dir, ctx.policy());
+    let mut files: Vec<(String, &str)> = ids
+        .iter()
+        .map(|id| {
+            let file = if is_reuse {
+                reuse::license_path(id)
+            } else {
+                file_name(id, ids.len() == 1)
+            };
+            (file, *id)
+        })
+        .collect();
+    // REUSE expects the texts of the exceptions in `LICENSES`, too.
+    if is_reuse {
+        files.extend(
+            exception_ids(license)
+                .into_iter()
+                .map(|id| (reuse::license_path(id), id)),
+        );
+    }
+    let (with_template, without_template): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .partition(|(_, id)| TEMPLATES.iter().any(|(template_id, _)| template_id == id));
+    for (_, id) in &without_template {
+        println!(
+            "There is no bundled template for `{}`, add its text manually.",
+            id
+        );
+    }
+    if with_template.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<&str> = with_template
+        .iter()
+        .map(|(file, _)| file.as_str())
+        .collect();
+    if ctx.is_dry_run() {
+        println!("Would offer to write {} (dry run).", names.join(", "));
+        return Ok(());
+    }
+    if !ctx.confirm(&format!(
+        "Write {} from the bundled templates? (Y/n)",
+        names.join(", ")
+    ))? {
+        return Ok(());
+    }
+
+    let year = current_year();
+    let holder = copyright_holder(package, dir);
+    let mut written = Vec::new();
+    for (file, id) in with_template {
+        if dir.join(&file).exists()
+            && !prompt_default(format!("{} already exists. Overwrite it?", file), false)?
+        {
+            continue;
+        }
+        let text = license_text(id, year, &holder).expect("the license has a template");
+        ctx.write_after_save(&file, text);
+        written.push(file);
+    }
+    if !written.is_empty() {
+        println!(
+            "{} will be written once the manifest is saved.",
+            written.join(", ")
+        );
+    }
+    if let Some(Inheritable::Set(license_file)) = &package.license_file {
+        if !written.is_empty() && is_license_file_replaced(license_file, &written, dir) {
+            package.license_file = None;
+            println!(
+                "Removed `license-file` since the license texts are found by their SPDX identifiers."
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Whether `license-file` no longer refers to a text of the user, i.e. the file was
+/// overwritten with a written license text or doesn't exist.
+fn is_license_file_replaced(license_file: &Path, written: &[String], dir: &Path) -> bool {
+    written.iter().any(|file| Path::new(file) == license_file) || !dir.join(license_file).is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn license_files_are_named_by_convention() {
+        let ids = license_ids("MIT OR Apache-2.0");
+        assert_eq!(ids, ["MIT", "Apache-2.0"]);
+        let names: Vec<String> = ids.iter().map(|id| file_name(id, false)).collect();
+        assert_eq!(names, ["LICENSE-MIT", "LICENSE-APACHE"]);
+        assert_eq!(file_name("MIT", true), "LICENSE");
+        assert_eq!(
+            exception_ids("(Apache-2.0 WITH LLVM-exception) OR MIT"),
+            ["LLVM-exception"]
+        );
+        assert!(license_text("LLVM-exception", 2024, "Jane Doe").is_some());
+    }
+
+    #[test]
+    fn own_license_files_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("LICENSE"), "Proprietary").unwrap();
+        std::fs::write(dir.path().join("COPYING"), "Proprietary").unwrap();
+        let written = ["LICENSE".to_string()];
+        let replaced = |file: &str| is_license_file_replaced(Path::new(file), &written, dir.path());
+        assert!(replaced("LICENSE"));
+        assert!(!replaced("COPYING"));
+        assert!(replaced("LICENSE.txt"));
+    }
+
+    #[test]
+    fn templates_are_filled_in() {
+        let text = license_text("MIT", 2024, "Jane Doe").unwrap();
+        assert!(text.contains("Copyright (c) 2024 Jane Doe\n"));
+        assert!(license_text("GPL-3.0-only", 2024, "Jane Doe").is_none());
+    }
+}
@@ -14,11 +14,185 @@
 //! cargo fill
 //! ```
 //!
+//! The fields can also be filled without prompting, e.g. in CI scripts:
+//!
+//! ```console
+//! cargo fill --yes --license "MIT OR Apache-2.0" --repository-from-git --rust-version-from-msrv
+//! ```
+//!
+//! The answers can also be kept in a TOML or JSON file that maps the fields to values or
+//! strategies, i.e. `from-git`, `msrv`, `docs-rs` or `skip`. The fields that are not in the file
+//! are still prompted for:
+//!
+//! ```toml
+//! repository = "from-git"
+//! license = "MIT OR Apache-2.0"
+//! rust-version = "msrv"
+//! ```
+//!
+//! ```console
+//! cargo fill --answers answers.toml
+//! ```
+//!
+//! Use `--record answers.toml` to save the answers given in an interactive session and replay
+//! them on the next crate.
+//!
+//! In a workspace, every member is filled in turn. Use `-p <NAME>` to choose the members:
+//!
+//! ```console
+//! cargo fill --workspace -p foo -p bar
+//! ```
+//!
+//! The metadata shared by the members, e.g. `license` or `rust-version`, can be filled once
+//! in `[workspace.package]` and inherited by the members with `field.workspace = true`.
+//!
+//! To fill the fields that can be derived with confidence without any questions, run
+//!
+//! ```console
+//! cargo fill fix
+//! ```
+//!
+//! It fills `authors` from git config, `documentation` with docs.rs for library crates, `readme`
+//! with an existing README file, `repository` from the git remote and `license-file` with a
+//! detected license file, prints what it did and leaves the ambiguous fields alone.
+//!
+//! To gate merges on complete manifests, check the metadata without prompting. Missing descriptions
+//! and licenses, unknown categories, too many keywords and malformed `rust-version`s are reported,
+//! and the command exits with `1` if any errors are found:
+//!
+//! ```console
+//! cargo fill check
+//! ```
+//!
+//! Like the errors of `rustc`, each finding shows the offending line of `Cargo.toml` with the key
+//! or value underlined, and a suggestion when there is one:
+//!
+//! ```text
+//! error: unknown category `comand-line-utilities`
+//!  --> Cargo.toml:9:15
+//!   |
+//! 9 | categories = ["comand-line-utilities"]
+//!   |               ^^^^^^^^^^^^^^^^^^^^^^^
+//!   = help: did you mean `command-line-utilities`?
+//! ```
+//!
+//! In CI, the findings can be reported as GitHub Actions annotations, as a SARIF log for code
+//! scanning or as JUnit XML for test dashboards. They point to the offending key or value in `Cargo.toml`,
+//! or to the `[package]` header when the key is missing:
+//!
+//! ```console
+//! cargo fill check --format github
+//! cargo fill check --format sarif --output cargo-fill.sarif
+//! cargo fill check --workspace --format junit --output cargo-fill.xml
+//! ```
+//!
+//! Before a release, check that the members agree on the license, repository, authors,
+//! `rust-version`, edition and keywords:
+//!
+//! ```console
+//! cargo fill check --workspace
+//! ```
+//!
+//! The check only reports the mismatches. To unify them, choose the values with
+//!
+//! ```console
+//! cargo fill unify
+//! ```
+//!
+//! Like other cargo subcommands, `cargo fill` works with the manifest in the current directory
+//! unless `--manifest-path` is given:
+//!
+//! ```console
+//! cargo fill --manifest-path path/to/Cargo.toml
+//! ```
+//!
+//! See `cargo fill --help` for the full list of flags.
+//!
+//! ## Configuration
+//!
+//! The defaults offered as the first option of the menus can be set in
+//! `~/.config/cargo-fill/config.toml` (or `$XDG_CONFIG_HOME/cargo-fill/config.toml`).
+//! Like in the answers files, a default is a value or a strategy, optionally with a label
+//! shown next to it. `{name}` is replaced with the name of the package:
+//!
+//! ```toml
+//! [defaults]
+//! authors = { value = "from-git", label = "recommended" }
+//! license = { value = "MIT OR Apache-2.0", label = "permissive" }
+//! homepage = "https://example.com/{name}"
+//! ```
+//!
+//! ## Policy
+//!
+//! Teams can declare rules for the manifests in `.cargo-fill.toml` files. The files in the directory
+//! of the manifest and its parents, up to the root of the git repository, are merged, with the
+//! settings of the closer files taking precedence:
+//!
+//! ```toml
+//! # The fields that must be set.
+//! required = ["description", "license", "repository"]
+//! # The only licenses that can be chosen.
+//! allowed-licenses = ["MIT", "Apache-2.0", "MIT OR Apache-2.0"]
+//! # The only licenses that the dependencies in `Cargo.lock` can use, as SPDX identifiers.
+//! allowed-dependency-licenses = ["MIT", "Apache-2.0", "BSD-3-Clause", "ISC", "Unicode-3.0"]
+//! # The prefix of the repository URLs.
+//! repository-prefix = "https://github.com/acme/"
+//! # The only registries that the packages can be published to.
+//! publish = ["acme"]
+//! # The fields that are never prompted for.
+//! never-ask = ["homepage"]
+//! # Whether the packages follow the REUSE specification, with the license texts in `LICENSES`
+//! # and `SPDX-License-Identifier` headers in the source files. Implied by a `LICENSES` directory.
+//! reuse = true
+//!
+//! # The values that are always filled, like in the answers files.
+//! [fixed]
+//! authors = ["Acme Developers <dev@acme.com>"]
+//!
+//! # The severities of the findings of `cargo fill check` per field: `error`, `warning` or `allow`.
+//! [severity]
+//! repository = "error"
+//! ```
+//!
+//! ## Exit codes
+//!
+//! * `0`: success.
+//! * `1`: a check found problems.
+//! * `2`: invalid command-line arguments or answers.
+//! * `3`: invalid manifest.
+//! * `4`: I/O failure.
+//! * `5`: an external tool, e.g. `git` or `cargo-msrv`, failed.
+//! * `6`: the manifest violates the policy.
+//! * `130`: aborted by the user, e.g. with Ctrl-C.
+//!
 //! ## Features
 //!
 //! * Fills all known fields in the `[package]` section of `Cargo.toml`.
+//! * Only asks about the fields that are missing, unless `--all` is given.
+//! * Fills all members of a workspace or the ones chosen with `-p`.
+//! * Fills `[workspace.package]` and makes the members inherit the shared metadata.
+//! * Reports the metadata drift between workspace members and unifies it with `cargo fill unify`.
+//! * Fills the fields that can be derived with confidence without prompting with `cargo fill fix`.
+//! * Reports the missing and invalid metadata in CI with `cargo fill check`.
+//! * Reports the findings as GitHub Actions annotations, SARIF or JUnit XML.
+//! * Underlines the offending values and suggests fixes, e.g. for misspelled categories.
+//! * Validates the license as an SPDX expression, suggests the closest identifiers for unknown ones and rewrites the legacy forms, e.g. `MIT/Apache-2.0` to `MIT OR Apache-2.0`.
+//! * Searches the SPDX license list, showing the full names and whether the licenses are OSI approved or copyleft, and combines several picked licenses into one expression.
+//! * Writes the license texts, e.g. `LICENSE-MIT` and `LICENSE-APACHE`, from bundled templates with the year and the copyright holder filled in.
+//! * Audits the licenses of the dependencies offline, from `Cargo.lock` and the local registry cache, against the license of the package and the allow-list of the policy.
+//! * Supports the REUSE specification: writes `LICENSES/<id>.txt` and reports, and optionally fixes, the source files whose SPDX header is missing or disagrees with the license.
+//! * Validates the categories against the bundled crates.io catalog, lists them with their descriptions, suggests the intended ones, e.g. `command-line-utilities` for `cli`, and enforces the limit of 5.
+//! * Browses the category hierarchy in a terminal, opening the subcategories and toggling up to 5 categories.
+//! * Suggests the categories from the crate, e.g. `no-std` for `#![no_std]` or `development-tools::cargo-plugins` for a `cargo-*` binary.
+//! * Validates the keywords against the rules of crates.io, explaining each rejection, and lowercases them and drops the duplicates.
 //! * Allows using `cargo-msrv` to fill the `rust-version` field.
 //! * Allows using `git config --get <user.name|user.email|remote.origin.url>` to guess the `authors` and `repository` fields.
+//! * Can be run non-interactively with a flag per field and `--yes`.
+//! * Reads the answers from a file and records the answers of a session for replaying.
+//! * Offers the defaults from the user config first.
+//! * Enforces the policy of the team or the organization declared in `.cargo-fill.toml`.
+//! * Shows a diff of the changes before saving them, or only shows it with `--dry-run`.
+//! * Edits `Cargo.toml` in place, preserving comments, formatting and the order of the keys.
 //!
 //! ## License
 //!
@@ -27,363 +201,917 @@
 //! [Apache License, Version 2.0]: https://www.apache.org/licenses/LICENSE-2.0
 //! [MIT license]: https://opensource.org/licenses/MIT
 
-use cargo_toml::{Inheritable, OptionalFile, Package};
-use std::path::PathBuf;
+use cargo_toml::{Edition, Inheritable, OptionalFile, Package, Publish};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 // TODO: replace promptly with a prompt library
 // that supports reusing the buffer.
-use promptly::{prompt, ReadlineError};
+use promptly::prompt;
 
+mod answers;
+mod audit;
+mod categories;
+mod cli;
+mod config;
+mod context;
+mod diff;
+mod drift;
+mod error;
 mod fill_miscellaneous;
 mod fill_rust_version;
+mod fix;
+mod license;
+mod license_files;
+mod manifest;
+mod menu;
+mod policy;
+mod report;
+mod reuse;
+mod validate;
+mod workspace;
 
+use answers::{split_comma_separated, Answer, Answers};
+use cli::{Args, CheckArgs, Command, FixArgs, UnifyArgs};
+use config::{Config, FieldDefault};
 use const_format::formatcp;
+use context::Context;
+use error::{Error, Result};
 use fill_miscellaneous::fill_miscellaneous;
 use fill_rust_version::fill_rust_version;
+use license::{normalize_license, InvalidLicense};
+use license_files::offer_license_files;
+use manifest::ManifestFile;
+use menu::{Choice, Menu};
+use report::{Diagnostic, Format};
+use validate::{
+    normalize_keywords, validate_categories, validate_keywords, MAX_CATEGORIES, MAX_KEYWORDS,
+};
+use workspace::Workspace;
+
+/// Returns the absolute path to the manifest given with `--manifest-path`
+/// or to `Cargo.toml` in the current directory.
+fn manifest_path(manifest_path: Option<&Path>) -> Result<PathBuf> {
+    let Some(path) = manifest_path else {
+        let mut cur_dir = std::env::current_dir().map_err(|e| Error::io(".", e))?;
+        cur_dir.push("Cargo.toml");
+        return Ok(cur_dir);
+    };
+    if path.file_name() != Some("Cargo.toml".as_ref()) {
+        return Err(Error::Usage(format!(
+            "the manifest path must be a path to a Cargo.toml file: {}",
+            path.display()
+        )));
+    }
+    std::path::absolute(path).map_err(|e| Error::io(path, e))
+}
 
-fn read_toml() -> cargo_toml::Manifest {
-    let mut cur_dir = std::env::current_dir()
-        .unwrap_or_else(|e| panic!("Failed to get the current directory: {}", e));
-    cur_dir.push("Cargo.toml");
-    let toml = std::fs::read_to_string(cur_dir)
-        .unwrap_or_else(|e| panic!("Failed to read Cargo.toml: {}", e));
-    toml::from_str(&toml).unwrap_or_else(|e| panic!("Failed to parse Cargo.toml: {}", e))
+fn git_config(key: &str, dir: &Path) -> Result<String> {
+    let command = format!("git config --get {}", key);
+    let output = std::process::Command::new("git")
+        .args(["config", "--get", key])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| Error::tool(&command, format!("is git installed? {}", e)))?;
+    if !output.status.success() {
+        return Err(Error::tool(&command, format!("`{}` is not set", key)));
+    }
+    let output = String::from_utf8(output.stdout)
+        .map_err(|e| Error::tool(&command, format!("the output is not UTF-8: {}", e)))?;
+    Ok(output.trim().to_string())
+}
+
+fn git_author(dir: &Path) -> Result<String> {
+    let name = git_config("user.name", dir)?;
+    let email = git_config("user.email", dir)?;
+    Ok(format!("{} <{}>", name, email))
+}
+
+fn git_repository(dir: &Path) -> Result<String> {
+    let url = git_config("remote.origin.url", dir)?;
+    let url = url.strip_suffix(".git").unwrap_or(&url);
+    Ok(url.to_string())
+}
+
+/// Reports the failure of an external tool before falling back to manual entry.
+fn report_fallback(e: &Error) {
+    eprintln!("error: {}", e);
+    println!("Falling back to manual entry.");
+}
+
+fn fill_authors(package: &mut Package, ctx: &Context) -> Result<()> {
+    let authors = match ctx.answer("authors") {
+        Some(Answer::FromGit) => match git_author(ctx.manifest_dir()) {
+            Ok(author) => vec![author],
+            Err(e) if ctx.is_interactive() => {
+                report_fallback(&e);
+                prompt_manual_authors()?
+            }
+            Err(e) => return Err(e),
+        },
+        Some(answer) => split_comma_separated(answer.value("authors")?),
+        None if !ctx.is_interactive() => return Ok(()),
+        None => match prompt_authors(&package.name, ctx)? {
+            Some(authors) => authors,
+            None => return Ok(()),
+        },
+    };
+    package.authors.set(authors);
+    Ok(())
+}
+
+fn prompt_manual_authors() -> Result<Vec<String>> {
+    let authors: String = prompt(
+        "Please enter comma-separated authors, e.g. `Dmitrii Demenev <demenev.dmitriy1@gmail.com>`\n",
+    )?;
+    Ok(split_comma_separated(&authors))
 }
 
-fn fill_authors(package: &mut Package) -> Result<(), ReadlineError> {
+fn prompt_authors(package_name: &str, ctx: &Context) -> Result<Option<Vec<String>>> {
     println!("Filling the `authors` field.");
     println!("Description: \"The authors of the package.\"");
     // TODO: find ways to obtain the author's info with his permission
 
+    let menu = Menu::new("Please choose the method of entering the authors.")
+        .option(
+            "Extract a single author from git config.",
+            Choice::Answer(Answer::FromGit),
+        )
+        .option("Enter the authors manually.", Choice::Manual)
+        .with_default(ctx.field_default("authors", package_name))
+        .required(ctx.policy().is_required("authors"));
     let authors = loop {
-        let c: String = prompt(
-            "Please choose the method of entering the authors.\n\
-            \n\
-            1. Extract a single author from git config.\n\
-            2. Enter the authors manually.\n\
-            ",
-        )?;
-        match c.as_str() {
-            "1" => {
-                let name: Vec<u8> = std::process::Command::new("git")
-                    .args(&["config", "--get", "user.name"])
-                    .output()
-                    .unwrap_or_else(|e| panic!("Failed to run `git config --get user.name`: {}", e))
-                    .stdout;
-                let name = String::from_utf8(name).unwrap_or_else(|e| panic!("Failed to parse the result of `git config --get user.name` as a UTF-8 string: {}", e));
-                let email: Vec<u8> = std::process::Command::new("git")
-                    .args(&["config", "--get", "user.email"])
-                    .output()
-                    .unwrap_or_else(|e| {
-                        panic!("Failed to run `git config --get user.email`: {}", e)
-                    })
-                    .stdout;
-                let email = String::from_utf8(email).unwrap_or_else(|e| panic!("Failed to parse the result of `git config --get user.email` as a UTF-8 string: {}", e));
-                let author = format!("{} <{}>", name.trim(), email.trim());
+        match menu.choose()? {
+            Choice::Answer(Answer::FromGit) => {
+                let author = match git_author(ctx.manifest_dir()) {
+                    Ok(author) => author,
+                    Err(e) => {
+                        report_fallback(&e);
+                        break prompt_manual_authors()?;
+                    }
+                };
                 println!("Extracted author: {}", author);
                 if !prompt("Is this correct? (Y/n)")? {
                     continue;
                 }
+                ctx.used_strategy(Answer::FromGit);
                 break vec![author];
             }
-            "2" => {
-                let authors: String = prompt(
-                    "Please enter comma-separated authors, e.g. `Dmitrii Demenev <demenev.dmitriy1@gmail.com>`\n",
-                )?;
-                let authors = authors
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect::<Vec<_>>();
-                break authors;
-            }
-            _ => println!("Invalid input."),
+            Choice::Answer(Answer::Skip) => return Ok(None),
+            Choice::Answer(answer) => break split_comma_separated(answer.value("authors")?),
+            Choice::Manual => break prompt_manual_authors()?,
         }
     };
-    package.authors.set(authors);
     println!();
+    Ok(Some(authors))
+}
+
+fn parse_edition(edition: &str) -> Option<Edition> {
+    toml::Value::String(edition.to_string()).try_into().ok()
+}
+
+fn edition_from_answer(answer: &Answer) -> Result<Edition> {
+    let edition = answer.value("edition")?;
+    parse_edition(edition).ok_or_else(|| Error::Usage(format!("unknown edition `{}`", edition)))
+}
+
+fn fill_edition(package: &mut Package, ctx: &Context) -> Result<()> {
+    let edition = match ctx.answer("edition").map(edition_from_answer) {
+        Some(Ok(edition)) => edition,
+        Some(Err(e)) if !ctx.is_interactive() => return Err(e),
+        None if !ctx.is_interactive() => return Ok(()),
+        answer => {
+            if let Some(Err(e)) = answer {
+                report_fallback(&e);
+            }
+            match prompt_edition(&package.name, ctx)? {
+                Some(edition) => edition,
+                None => return Ok(()),
+            }
+        }
+    };
+    package.edition = Inheritable::Set(edition);
     Ok(())
 }
 
-fn fill_description(package: &mut Package) -> Result<(), ReadlineError> {
-    println!("Filling the `description` field.");
-    println!("Description: \"A description of the package.\"");
-    let description: String = prompt("Please enter the crate description")?;
-    package.description = Some(Inheritable::Set(description));
+fn prompt_edition(package_name: &str, ctx: &Context) -> Result<Option<Edition>> {
+    println!("Filling the `edition` field.");
+    println!("Description: \"The Rust edition.\"");
+    let menu = Menu::new("Please choose the method of entering the edition.")
+        .option("Enter the edition manually.", Choice::Manual)
+        .option("Skip.", Choice::Answer(Answer::Skip))
+        .with_default(ctx.field_default("edition", package_name))
+        .required(ctx.policy().is_required("edition"));
+    let edition = loop {
+        match menu.choose()? {
+            Choice::Answer(Answer::Skip) => return Ok(None),
+            Choice::Answer(answer) => match edition_from_answer(&answer) {
+                Ok(edition) => break edition,
+                Err(e) => println!("{}", e),
+            },
+            Choice::Manual => {
+                let edition: String = prompt("Please enter the edition, e.g. `2021`")?;
+                match parse_edition(&edition) {
+                    Some(edition) => break edition,
+                    None => println!("Unknown edition `{}`.", edition),
+                }
+            }
+        }
+    };
     println!();
+    Ok(Some(edition))
+}
+
+fn fill_description(package: &mut Package, ctx: &Context) -> Result<()> {
+    let description = match ctx.answer("description") {
+        Some(answer) => answer.value("description")?.to_string(),
+        None if !ctx.is_interactive() => return Ok(()),
+        None => {
+            println!("Filling the `description` field.");
+            println!("Description: \"A description of the package.\"");
+            let description: String = prompt("Please enter the crate description")?;
+            println!();
+            description
+        }
+    };
+    package.description = Some(Inheritable::Set(description));
     Ok(())
 }
 
-fn fill_documentation(package: &mut Package) -> Result<(), ReadlineError> {
+fn fill_documentation(package: &mut Package, ctx: &Context) -> Result<()> {
+    let docs_rs = format!("https://docs.rs/{}", package.name);
+    let documentation = match ctx.answer("documentation") {
+        Some(Answer::DocsRs) => docs_rs,
+        Some(answer) => answer.value("documentation")?.to_string(),
+        None if !ctx.is_interactive() => return Ok(()),
+        None => match prompt_documentation(&package.name, docs_rs, ctx)? {
+            Some(documentation) => documentation,
+            None => return Ok(()),
+        },
+    };
+    package.documentation = Some(Inheritable::Set(documentation));
+    Ok(())
+}
+
+fn prompt_documentation(
+    package_name: &str,
+    docs_rs: String,
+    ctx: &Context,
+) -> Result<Option<String>> {
     println!("Filling the `documentation` field.");
     println!("Description: \"The URL of the package documentation.\"");
-    let documentation = loop {
-        let c: String = prompt(format!(
-            "Please choose the method of entering the documentation.\n\
-            \n\
-            1. Default to `https://docs.rs/{package_name}`.\n\
-            2. Enter the documentation URL manually.\n\
-            3. Skip (suggested for binary crates).\n\
-        ",
-            package_name = package.name
-        ))?;
-        match c.as_str() {
-            "1" => break format!("https://docs.rs/{}", package.name),
-            "2" => {
-                let url: String = prompt("Please enter the documentation URL")?;
-                break url;
-            }
-            "3" => return Ok(()),
-            _ => println!("Invalid input."),
+    let menu = Menu::new("Please choose the method of entering the documentation.")
+        .option(
+            format!("Default to `https://docs.rs/{package_name}`."),
+            Choice::Answer(Answer::DocsRs),
+        )
+        .option("Enter the documentation URL manually.", Choice::Manual)
+        .option(
+            "Skip (suggested for binary crates).",
+            Choice::Answer(Answer::Skip),
+        )
+        .with_default(ctx.field_default("documentation", package_name))
+        .required(ctx.policy().is_required("documentation"));
+    let documentation = match menu.choose()? {
+        Choice::Answer(Answer::DocsRs) => {
+            ctx.used_strategy(Answer::DocsRs);
+            docs_rs
         }
+        Choice::Answer(Answer::Skip) => return Ok(None),
+        Choice::Answer(answer) => answer.value("documentation")?.to_string(),
+        Choice::Manual => prompt("Please enter the documentation URL")?,
     };
-    package.documentation = Some(Inheritable::Set(documentation));
     println!();
+    Ok(Some(documentation))
+}
+
+fn fill_readme(package: &mut Package, ctx: &Context) -> Result<()> {
+    let readme = match ctx.answer("readme") {
+        Some(answer) => answer.value("readme")?.to_string(),
+        None if !ctx.is_interactive() => return Ok(()),
+        None => match prompt_readme(&package.name, ctx)? {
+            Some(readme) => readme,
+            None => return Ok(()),
+        },
+    };
+    let readme = OptionalFile::Path(PathBuf::from(readme));
+    package.readme = Inheritable::Set(readme);
     Ok(())
 }
 
-fn fill_readme(package: &mut Package) -> Result<(), ReadlineError> {
+fn prompt_readme(package_name: &str, ctx: &Context) -> Result<Option<String>> {
     println!("Filling the `readme` field.");
     println!("Description: \"Path to the package’s README file.\"");
-    let readme = loop {
-        let c: String = prompt(format!(
-            "Please choose the method of entering the README.\n\
-            \n\
-            1. Default to `README.md`.\n\
-            2. Enter the README path manually.\n\
-            3. Skip (discouraged).\n\
-        ",
-        ))?;
-        match c.as_str() {
-            "1" => break "README.md".to_string(),
-            "2" => {
-                let path: String = prompt("Please enter the README path")?;
-                break path;
-            }
-            "3" => return Ok(()),
-            _ => println!("Invalid input."),
-        }
+    let menu = Menu::new("Please choose the method of entering the README.")
+        .option("Enter the README path manually.", Choice::Manual)
+        .option("Skip (discouraged).", Choice::Answer(Answer::Skip))
+        .with_default(ctx.field_default("readme", package_name))
+        .required(ctx.policy().is_required("readme"));
+    let readme = match menu.choose()? {
+        Choice::Answer(Answer::Skip) => return Ok(None),
+        Choice::Answer(answer) => answer.value("readme")?.to_string(),
+        Choice::Manual => prompt("Please enter the README path")?,
     };
-    let readme = OptionalFile::Path(PathBuf::from(readme));
-    package.readme = Inheritable::Set(readme);
     println!();
+    Ok(Some(readme))
+}
+
+fn fill_homepage(package: &mut Package, ctx: &Context) -> Result<()> {
+    let homepage = match ctx.answer("homepage") {
+        Some(answer) => answer.value("homepage")?.to_string(),
+        None if !ctx.is_interactive() => return Ok(()),
+        None => match prompt_homepage(&package.name, ctx)? {
+            Some(homepage) => homepage,
+            None => return Ok(()),
+        },
+    };
+    package.homepage = Some(Inheritable::Set(homepage));
     Ok(())
 }
 
-fn fill_homepage(package: &mut Package) -> Result<(), ReadlineError> {
+fn prompt_homepage(package_name: &str, ctx: &Context) -> Result<Option<String>> {
     println!("Filling the `homepage` field.");
     println!("URL of the package homepage.");
-    loop {
-        let c: String = prompt(format!(
-            "Please choose the method of entering the homepage.\n\
-            \n\
-            1. Skip.\n\
-            2. Enter the homepage URL manually.\n\
-        "
-        ))?;
-        match c.as_str() {
-            "1" => break,
-            "2" => {
-                let url: String = prompt("Please enter the homepage URL")?;
-                package.homepage = Some(Inheritable::Set(url));
-                break;
-            }
-            _ => println!("Invalid input."),
-        }
-    }
+    let menu = Menu::new("Please choose the method of entering the homepage.")
+        .option("Skip.", Choice::Answer(Answer::Skip))
+        .option("Enter the homepage URL manually.", Choice::Manual)
+        .with_default(ctx.field_default("homepage", package_name))
+        .required(ctx.policy().is_required("homepage"));
+    let homepage = match menu.choose()? {
+        Choice::Answer(Answer::Skip) => return Ok(None),
+        Choice::Answer(answer) => answer.value("homepage")?.to_string(),
+        Choice::Manual => prompt("Please enter the homepage URL")?,
+    };
     println!();
+    Ok(Some(homepage))
+}
+
+fn fill_repository(package: &mut Package, ctx: &Context) -> Result<()> {
+    let repository = match ctx.answer("repository") {
+        Some(Answer::FromGit) => match git_repository(ctx.manifest_dir()) {
+            Ok(repository) => repository,
+            Err(e) if ctx.is_interactive() => {
+                report_fallback(&e);
+                prompt("Please enter the repository URL")?
+            }
+            Err(e) => return Err(e),
+        },
+        Some(answer) => answer.value("repository")?.to_string(),
+        None if !ctx.is_interactive() => return Ok(()),
+        None => match prompt_repository(&package.name, ctx)? {
+            Some(repository) => repository,
+            None => return Ok(()),
+        },
+    };
+    ctx.policy().check_repository(&repository)?;
+    package.repository = Some(Inheritable::Set(repository));
     Ok(())
 }
 
-fn fill_repository(package: &mut Package) -> Result<(), ReadlineError> {
+fn prompt_repository(package_name: &str, ctx: &Context) -> Result<Option<String>> {
     println!("Filling the `repository` field.");
     println!("Description: \"URL of the package source repository.\"");
+    let mut menu = Menu::new("Please choose the method of entering the repository.");
+    if let Some(prefix) = ctx.policy().repository_prefix() {
+        let url = format!("{}{}", prefix, package_name);
+        menu = menu.option(
+            format!("Default to `{}`.", url),
+            Choice::Answer(Answer::Value(url)),
+        );
+    }
+    let menu = menu
+        .option("Skip (discouraged).", Choice::Answer(Answer::Skip))
+        .option(
+            "Extract with `git config --get remote.origin.url`.",
+            Choice::Answer(Answer::FromGit),
+        )
+        .option("Enter the repository URL manually.", Choice::Manual)
+        .with_default(ctx.field_default("repository", package_name))
+        .required(ctx.policy().is_required("repository"));
     let repository = loop {
-        let c: String = prompt(
-            "Please choose the method of entering the repository.\n\
-            \n\
-            1. Skip (discouraged).\n\
-            2. Extract with `git config --get remote.origin.url`.\n\
-            3. Enter the repository URL manually.\n\
-            ",
-        )?;
-        match c.as_str() {
-            "1" => return Ok(()),
-            "2" => {
-                let name: Vec<u8> = std::process::Command::new("git")
-                    .args(&["config", "--get", "remote.origin.url"])
-                    .output()
-                    .unwrap_or_else(|e| {
-                        panic!("Failed to run `git config --get remote.origin.url`: {}", e)
-                    })
-                    .stdout;
-                let url = String::from_utf8(name).unwrap_or_else(|e| panic!("Failed to parse the result of `git config --get remote.origin.url` as a UTF-8 string: {}", e));
-
-                let url = url.trim();
-
-                let url = url.strip_suffix(".git").unwrap_or(url);
-
-                println!("Guessed repository: {}", url);
-                if !prompt("Is this correct? (Y/n)")? {
-                    continue;
+        let (url, strategy) = match menu.choose()? {
+            Choice::Answer(Answer::Skip) => return Ok(None),
+            Choice::Answer(Answer::FromGit) => match git_repository(ctx.manifest_dir()) {
+                Ok(url) => {
+                    println!("Guessed repository: {}", url);
+                    if !prompt("Is this correct? (Y/n)")? {
+                        continue;
+                    }
+                    (url, Some(Answer::FromGit))
                 }
-                break url.to_string();
-            }
-            "3" => {
-                let url: String = prompt("Please enter the repository URL")?;
-                break url;
-            }
-            _ => println!("Invalid input."),
+                Err(e) => {
+                    report_fallback(&e);
+                    (prompt("Please enter the repository URL")?, None)
+                }
+            },
+            Choice::Answer(answer) => (answer.value("repository")?.to_string(), None),
+            Choice::Manual => (prompt("Please enter the repository URL")?, None),
+        };
+        if let Err(e) = ctx.policy().check_repository(&url) {
+            println!("{}", e);
+            continue;
+        }
+        if let Some(strategy) = strategy {
+            ctx.used_strategy(strategy);
         }
+        break url;
     };
-    package.repository = Some(Inheritable::Set(repository));
     println!();
+    Ok(Some(repository))
+}
+
+/// Parses the license expression and reports if it was rewritten to its canonical form.
+fn normalize_license_verbose(license: &str) -> std::result::Result<String, InvalidLicense> {
+    let normalized = normalize_license(license)?;
+    if normalized != license {
+        println!("Rewrote the license `{}` to `{}`.", license, normalized);
+    }
+    Ok(normalized)
+}
+
+fn fill_license(package: &mut Package, ctx: &Context) -> Result<()> {
+    let license = match ctx.answer("license") {
+        Some(answer) => normalize_license_verbose(answer.value("license")?)
+            .map_err(|e| Error::Usage(e.to_string()))?,
+        None if !ctx.is_interactive() => return Ok(()),
+        None => match prompt_license(&package.name, ctx)? {
+            Some(license) => license,
+            None => return Ok(()),
+        },
+    };
+    ctx.policy().check_license(&license)?;
+    // The audit is advisory here, so a broken lockfile doesn't stop the filling.
+    match audit::audit_filled(&package.name, &license, ctx.manifest_dir(), ctx.policy()) {
+        Ok(audit) => audit.print(),
+        Err(e) => eprintln!(
+            "warning: couldn't audit the licenses of the dependencies: {}",
+            e
+        ),
+    }
+    package.license = Some(Inheritable::Set(license.clone()));
+    if ctx.is_interactive() {
+        offer_license_files(package, ctx)?;
+        if reuse::is_enabled(ctx.manifest_dir(), ctx.policy()) {
+            reuse::offer_headers(&license, ctx)?;
+        }
+    } else {
+        drop_license_file(package, ctx);
+    }
     Ok(())
 }
 
-fn fill_license(package: &mut Package) -> Result<(), ReadlineError> {
+/// Removes `license-file` once `license` is set, unless it refers to a text of the user,
+/// which is then reported since cargo only needs one of them.
+fn drop_license_file(package: &mut Package, ctx: &Context) {
+    let Some(Inheritable::Set(license_file)) = package.license_file.clone() else {
+        return;
+    };
+    if ctx.manifest_dir().join(&license_file).is_file() {
+        println!(
+            "warning: both `license` and `license-file` are set, remove `license-file` \
+             unless {} holds a license that isn't in the SPDX list.",
+            license_file.display()
+        );
+    } else {
+        package.license_file = None;
+        println!(
+            "Removed `license-file` since {} doesn't exist.",
+            license_file.display()
+        );
+    }
+}
+
+fn prompt_license(package_name: &str, ctx: &Context) -> Result<Option<String>> {
     println!("Filling the `license` field.");
     println!("Description: \"The package license.\"");
+    let mut menu = Menu::new("Please choose the method of entering the license.");
+    for license in ctx.policy().allowed_licenses() {
+        menu = menu.option(
+            format!("Use `{}`.", license),
+            Choice::Answer(Answer::Value(license.clone())),
+        );
+    }
+    let menu = menu
+        .option(
+            "Search the SPDX license list or enter an expression manually.",
+            Choice::Manual,
+        )
+        .with_default(ctx.field_default("license", package_name))
+        .required(ctx.policy().is_required("license"));
     let license = loop {
-        let c: String = prompt(
-            "Please choose the method of entering the license.\n\
-            \n\
-            1. Default to `MIT OR Apache-2.0` (permissive).\n\
-            2. Enter the license SPDX identifier manually.\n\
-            ",
-        )?;
-        match c.as_str() {
-            "1" => break "MIT OR Apache-2.0".to_string(),
-            "2" => {
-                let license: String = prompt("Please enter the license")?;
-                break license;
+        let license = match menu.choose()? {
+            Choice::Answer(Answer::Skip) => return Ok(None),
+            Choice::Answer(answer) => answer.value("license")?.to_string(),
+            Choice::Manual => license::pick_license()?,
+        };
+        let license = match normalize_license_verbose(&license) {
+            Ok(license) => license,
+            Err(e) => {
+                println!("{}", e);
+                continue;
             }
-            _ => println!("Invalid input."),
+        };
+        match ctx.policy().check_license(&license) {
+            Ok(()) => break license,
+            Err(e) => println!("{}", e),
         }
     };
-    package.license = Some(Inheritable::Set(license));
     println!();
+    Ok(Some(license))
+}
+
+fn fill_license_file(package: &mut Package, ctx: &Context) -> Result<()> {
+    let license_file = match ctx.answer("license-file") {
+        Some(answer) => answer.value("license-file")?.to_string(),
+        None if !ctx.is_interactive() => return Ok(()),
+        // The texts of the SPDX licenses are found by their identifiers.
+        None if package.license.is_some() => return Ok(()),
+        None => match prompt_license_file(&package.name, ctx)? {
+            Some(license_file) => license_file,
+            None => return Ok(()),
+        },
+    };
+    let license_file = PathBuf::from(license_file);
+    package.license_file = Some(Inheritable::Set(license_file));
     Ok(())
 }
 
-fn fill_license_file(package: &mut Package) -> Result<(), ReadlineError> {
+fn prompt_license_file(package_name: &str, ctx: &Context) -> Result<Option<String>> {
     println!("Filling the `license-file` field.");
     println!("Description: \"Path to the text of the license.\"");
-    let license_file = loop {
-        let c: String = prompt(
-            "Please choose the method of entering the license file.\n\
-            \n\
-            1. Default to `LICENSE`.\n\
-            2. Enter the license file path manually.\n\
-            3. Skip.\n\
-            ",
-        )?;
-        match c.as_str() {
-            "1" => break "LICENSE".to_string(),
-            "2" => {
-                let path: String = prompt("Please enter the license file path")?;
-                break path;
+    let menu = Menu::new("Please choose the method of entering the license file.")
+        .option("Enter the license file path manually.", Choice::Manual)
+        .option("Skip.", Choice::Answer(Answer::Skip))
+        .with_default(ctx.field_default("license-file", package_name))
+        .required(ctx.policy().is_required("license-file"));
+    let license_file = match menu.choose()? {
+        Choice::Answer(Answer::Skip) => return Ok(None),
+        Choice::Answer(answer) => answer.value("license-file")?.to_string(),
+        Choice::Manual => prompt("Please enter the license file path")?,
+    };
+    println!();
+    Ok(Some(license_file))
+}
+
+fn keywords_from_answer(answer: &Answer) -> Result<Vec<String>> {
+    let keywords = normalize_keywords(&split_comma_separated(answer.value("keywords")?));
+    validate_keywords(&keywords).map_err(Error::Usage)?;
+    Ok(keywords)
+}
+
+fn fill_keywords(package: &mut Package, ctx: &Context) -> Result<()> {
+    let keywords = match ctx.answer("keywords").map(keywords_from_answer) {
+        Some(Ok(keywords)) => keywords,
+        Some(Err(e)) if !ctx.is_interactive() => return Err(e),
+        None if !ctx.is_interactive() => return Ok(()),
+        answer => {
+            if let Some(Err(e)) = answer {
+                report_fallback(&e);
+            }
+            match prompt_keywords(&package.name, ctx)? {
+                Some(keywords) => keywords,
+                None => return Ok(()),
             }
-            "3" => return Ok(()),
-            _ => println!("Invalid input."),
         }
     };
-    let license_file = PathBuf::from(license_file);
-    package.license_file = Some(Inheritable::Set(license_file));
-    println!();
+    package.keywords = Inheritable::Set(keywords);
     Ok(())
 }
 
-fn fill_keywords(package: &mut Package) -> Result<(), ReadlineError> {
+fn prompt_keywords(package_name: &str, ctx: &Context) -> Result<Option<Vec<String>>> {
     println!("Filling the `keywords` field.");
     println!("Description: \"The keywords of the package.\"");
 
-    const MAX_KEYWORDS: usize = 5;
-
+    let menu = Menu::new("Please choose the method of entering the keywords.")
+        .option("Skip.", Choice::Answer(Answer::Skip))
+        .option(
+            formatcp!(
+                "Enter the keywords manually (no more than {MAX_KEYWORDS} keywords allowed)."
+            ),
+            Choice::Manual,
+        )
+        .with_default(ctx.field_default("keywords", package_name))
+        .required(ctx.policy().is_required("keywords"));
     let keywords = loop {
-        let c: String = prompt(formatcp!(
-            "Please choose the method of entering the keywords.\n\
-            \n\
-            1. Skip.\n\
-            2. Enter the keywords manually (no more than {MAX_KEYWORDS} keywords allowed).\n\
-            "
-        ))?;
-        match c.as_str() {
-            "1" => return Ok(()),
-            "2" => {
+        match menu.choose()? {
+            Choice::Answer(Answer::Skip) => return Ok(None),
+            Choice::Answer(answer) => match keywords_from_answer(&answer) {
+                Ok(keywords) => break keywords,
+                Err(e) => println!("{}", e),
+            },
+            Choice::Manual => {
                 let keywords: String = prompt("Please enter the keywords separated by comma")?;
-                let keywords = keywords
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect::<Vec<_>>();
-                if keywords.len() > MAX_KEYWORDS {
-                    println!("Too many keywords ({} > {}).", keywords.len(), MAX_KEYWORDS);
-                    continue;
+                let keywords = normalize_keywords(&split_comma_separated(&keywords));
+                match validate_keywords(&keywords) {
+                    Ok(()) => break keywords,
+                    Err(e) => println!("{}", e),
                 }
-                break keywords;
             }
-            _ => println!("Invalid input."),
         }
     };
-
-    package.keywords = Inheritable::Set(keywords);
     println!();
+    Ok(Some(keywords))
+}
+
+/// Splits the comma-separated categories, dropping the empty ones, e.g. after a trailing comma.
+fn split_categories(categories: &str) -> Vec<String> {
+    split_comma_separated(categories)
+        .into_iter()
+        .filter(|category| !category.is_empty())
+        .collect()
+}
+
+fn categories_from_answer(answer: &Answer) -> Result<Vec<String>> {
+    let categories = split_categories(answer.value("categories")?);
+    validate_categories(&categories).map_err(Error::Usage)?;
+    Ok(categories)
+}
+
+fn fill_categories(package: &mut Package, ctx: &Context) -> Result<()> {
+    let categories = match ctx.answer("categories").map(categories_from_answer) {
+        Some(Ok(categories)) => categories,
+        Some(Err(e)) if !ctx.is_interactive() => return Err(e),
+        None if !ctx.is_interactive() => return Ok(()),
+        answer => {
+            if let Some(Err(e)) = answer {
+                report_fallback(&e);
+            }
+            match prompt_categories(package, ctx)? {
+                Some(categories) => categories,
+                None => return Ok(()),
+            }
+        }
+    };
+    package.categories = Inheritable::Set(categories);
     Ok(())
 }
 
-fn fill_categories(package: &mut Package) -> Result<(), ReadlineError> {
+fn prompt_categories(package: &Package, ctx: &Context) -> Result<Option<Vec<String>>> {
     println!("Filling the `categories` field.");
     println!("Description: \"Categories of the package.\"");
-    println!("See <https://crates.io/category_slugs> for the list of categories.");
+    let detected = categories::detect_categories(package, ctx.manifest_dir());
+    let detected_default = (!detected.is_empty()).then(|| FieldDefault {
+        answer: Answer::Value(detected.join(", ")),
+        label: Some("detected from the crate".to_string()),
+    });
+    let menu = Menu::new("Please choose the method of entering the categories.")
+        .option("Skip.", Choice::Answer(Answer::Skip))
+        .option(
+            formatcp!("Choose the categories (no more than {MAX_CATEGORIES} categories allowed)."),
+            Choice::Manual,
+        )
+        .with_default(
+            ctx.field_default("categories", &package.name)
+                .or(detected_default),
+        )
+        .required(ctx.policy().is_required("categories"));
     let categories = loop {
-        let c: String = prompt(
-            "Please choose the method of entering the categories.\n\
-            \n\
-            1. Skip.\n\
-            2. Enter the categories manually.\n\
-            ",
-        )?;
-        match c.as_str() {
-            "1" => return Ok(()),
-            "2" => {
-                let categories: String =
-                    prompt("Please enter the categories separated by comma\n")?;
-                break categories;
+        match menu.choose()? {
+            Choice::Answer(Answer::Skip) => return Ok(None),
+            Choice::Answer(answer) => match categories_from_answer(&answer) {
+                Ok(categories) => break categories,
+                Err(e) => println!("{}", e),
+            },
+            Choice::Manual if std::io::stdin().is_terminal() => {
+                let categories = categories::browse_categories(&detected)?;
+                if !categories.is_empty() {
+                    break categories;
+                }
+            }
+            Choice::Manual => {
+                let categories: String = prompt(
+                    "Please enter the categories separated by comma, or `?` to list them\n",
+                )?;
+                if categories.trim() == "?" {
+                    for (category, description) in categories::CATEGORIES {
+                        println!("{}: {}", category, description);
+                    }
+                    continue;
+                }
+                let categories = split_categories(&categories);
+                match validate_categories(&categories) {
+                    Ok(()) => break categories,
+                    Err(e) => println!("{}", e),
+                }
             }
-            _ => println!("Invalid input."),
         }
     };
-    let categories = categories
-        .split(',')
-        .map(|s| s.trim().to_string())
-        .collect::<Vec<_>>();
-    package.categories = Inheritable::Set(categories);
     println!();
+    Ok(Some(categories))
+}
+
+fn fill_manifest(path: &Path, ctx: &Context) -> Result<()> {
+    let ctx = &ctx.for_manifest(path)?;
+    let mut file = ManifestFile::read(path)?;
+    let mut package = file.package()?;
+    let original = package.clone();
+    let package = &mut package;
+
+    ctx.fill(&file, package, "authors", fill_authors)?;
+    ctx.fill(&file, package, "edition", fill_edition)?;
+    ctx.fill(&file, package, "rust-version", fill_rust_version)?;
+    ctx.fill(&file, package, "description", fill_description)?;
+    ctx.fill(&file, package, "documentation", fill_documentation)?;
+    ctx.fill(&file, package, "readme", fill_readme)?;
+    ctx.fill(&file, package, "homepage", fill_homepage)?;
+    ctx.fill(&file, package, "repository", fill_repository)?;
+    ctx.fill(&file, package, "license", fill_license)?;
+    ctx.fill(&file, package, "license-file", fill_license_file)?;
+    ctx.fill(&file, package, "keywords", fill_keywords)?;
+    ctx.fill(&file, package, "categories", fill_categories)?;
+    // An existing `publish`, e.g. `publish = false`, is only checked and never replaced.
+    match ctx.policy().publish() {
+        Some(registries) if file.package_item("publish").is_none() => {
+            package.publish = Inheritable::Set(Publish::Registry(registries.to_vec()));
+        }
+        _ => ctx.policy().check_publish(&package.publish)?,
+    }
+    // A bunch of fields with niche use cases.
+    fill_miscellaneous(&file, package, ctx)?;
+
+    file.update_package(&original, package)?;
+    ctx.policy().check_required(&file)?;
+    review_and_save(&file, ctx)
+}
+
+fn review_and_save(file: &ManifestFile, ctx: &Context) -> Result<()> {
+    review_and_save_checked(file, ctx).map(|_| ())
+}
+
+/// Like [`review_and_save`] but returns whether the manifest on disk now has the changes,
+/// i.e. `false` if they were declined or only shown with `--dry-run`.
+fn review_and_save_checked(file: &ManifestFile, ctx: &Context) -> Result<bool> {
+    let path = file.path().display().to_string();
+    let Some(diff) = diff::unified_diff(&path, file.original(), &file.contents()) else {
+        println!("No changes to {}.", path);
+        ctx.write_pending_files()?;
+        return Ok(true);
+    };
+    println!("{}", diff);
+    if ctx.is_dry_run() || !ctx.confirm("Save the changes? (Y/n)")? {
+        ctx.discard_pending_files();
+        return Ok(false);
+    }
+    file.save()?;
+    ctx.write_pending_files()?;
+    Ok(true)
+}
+
+/// Returns the manifests of the chosen workspace members or of all of them.
+fn workspace_members(workspace: &Workspace, packages: &[String]) -> Result<Vec<PathBuf>> {
+    if packages.is_empty() {
+        Ok(workspace.members.clone())
+    } else {
+        workspace.select(packages)
+    }
+}
+
+/// Runs `cargo fill check`.
+///
+/// Returns `false` if the check failed.
+fn check(args: &CheckArgs, manifest_path: &Path) -> Result<bool> {
+    let single = !args.workspace && args.packages.is_empty() && !is_virtual(manifest_path)?;
+    let workspace = if single {
+        None
+    } else {
+        Some(Workspace::find_required(manifest_path)?)
+    };
+    let members = match &workspace {
+        Some(workspace) => workspace_members(workspace, &args.packages)?,
+        None => vec![manifest_path.to_path_buf()],
+    };
+
+    let mut diagnostics = validate::diagnostics(&members)?;
+    // The mismatches between the members are listed separately in the human format.
+    if let Some(workspace) = workspace.as_ref().filter(|_| args.format != Format::Human) {
+        diagnostics.extend(drift::diagnostics(workspace, &members)?);
+    }
+    let color = args.output.is_none() && diff::use_color();
+    let report = report::render(args.format, &members, &diagnostics, color);
+    match &args.output {
+        Some(path) => std::fs::write(path, report).map_err(|e| Error::io(path, e))?,
+        None => print!("{}", report),
+    }
+    let mut passed = !diagnostics.iter().any(Diagnostic::is_error);
+    if let Some(workspace) = workspace.as_ref().filter(|_| args.format == Format::Human) {
+        println!();
+        passed &= drift::check(workspace, &members)?;
+    }
+    Ok(passed)
+}
+
+/// Runs `cargo fill fix`.
+fn fix(args: &FixArgs, manifest_path: &Path) -> Result<()> {
+    if !args.workspace && args.packages.is_empty() && !is_virtual(manifest_path)? {
+        return fix::fix_manifest(manifest_path, args.dry_run);
+    }
+    let workspace = Workspace::find_required(manifest_path)?;
+    for member in workspace_members(&workspace, &args.packages)? {
+        println!("Fixing {}.\n", member.display());
+        fix::fix_manifest(&member, args.dry_run)?;
+        println!();
+    }
     Ok(())
 }
 
-fn main() {
-    let mut manifest = read_toml();
-    let package = manifest
+/// Runs `cargo fill unify`.
+fn unify(args: &UnifyArgs, manifest_path: &Path) -> Result<()> {
+    let workspace = Workspace::find_required(manifest_path)?;
+    let members = workspace_members(&workspace, &args.packages)?;
+    drift::unify(&workspace, &members, args.dry_run)
+}
+
+/// Whether the manifest has a `[workspace]` but no `[package]`.
+fn is_virtual(manifest_path: &Path) -> Result<bool> {
+    Ok(ManifestFile::read(manifest_path)?
+        .manifest()?
         .package
-        .as_mut()
-        .expect("Cargo.toml has no package section");
-
-    fill_authors(package).unwrap();
-    fill_rust_version(package).unwrap();
-    fill_description(package).unwrap();
-    fill_documentation(package).unwrap();
-    fill_readme(package).unwrap();
-    fill_homepage(package).unwrap();
-    fill_repository(package).unwrap();
-    fill_license(package).unwrap();
-    fill_license_file(package).unwrap();
-    fill_keywords(package).unwrap();
-    fill_categories(package).unwrap();
-    // A bunch of fields with niche use cases.
-    fill_miscellaneous(package).unwrap();
+        .is_none())
+}
+
+/// Runs `cargo fill`.
+fn fill(args: &Args) -> Result<()> {
+    let mut answers = match &args.answers {
+        Some(path) => Answers::read(path)?,
+        None => Answers::default(),
+    };
+    // The fields given on the command line take precedence over the answers file.
+    answers.extend(args.fields.answers());
+    let ctx = Context::new(answers, Config::load()?, args.yes, args.all, args.dry_run);
+
+    fill_manifests(args, &ctx)?;
+
+    if let Some(path) = &args.record {
+        ctx.recorded_answers().write(path)?;
+        println!("Recorded the answers to {}.", path.display());
+    }
+    Ok(())
+}
+
+fn fill_manifests(args: &Args, ctx: &Context) -> Result<()> {
+    let manifest_path = manifest_path(args.manifest_path.as_deref())?;
+    if !args.workspace && args.packages.is_empty() && !is_virtual(&manifest_path)? {
+        return fill_manifest(&manifest_path, ctx);
+    }
 
-    let toml = toml::ser::to_string_pretty(&manifest).unwrap();
-    println!("Cargo.toml:\n\n{}", &toml);
-    if prompt("Save the changes? (Y/n)").unwrap() {
-        std::fs::write("Cargo.toml", &toml).unwrap();
+    let workspace = Workspace::find_required(&manifest_path)?;
+    let members = workspace_members(&workspace, &args.packages)?;
+    if ctx.confirm("Fill the metadata shared by the members in `[workspace.package]`? (Y/n)")? {
+        if let Some(shared) = workspace.fill_package(ctx)? {
+            workspace.inherit(&members, &shared, ctx)?;
+        }
+    }
+    for member in members {
+        println!("Filling {}.\n", member.display());
+        fill_manifest(&member, ctx)?;
+        println!();
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = Args::parse_args();
+    let result = match &args.command {
+        Some(Command::Check(check_args)) => {
+            manifest_path(args.manifest_path.as_deref()).and_then(|path| check(check_args, &path))
+        }
+        Some(Command::Fix(fix_args)) => manifest_path(args.manifest_path.as_deref())
+            .and_then(|path| fix(fix_args, &path))
+            .map(|()| true),
+        Some(Command::Unify(unify_args)) => manifest_path(args.manifest_path.as_deref())
+            .and_then(|path| unify(unify_args, &path))
+            .map(|()| true),
+        None => fill(&args).map(|()| true),
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_manifest_path_is_resolved() {
+        let cur_dir = std::env::current_dir().unwrap();
+        assert_eq!(manifest_path(None).unwrap(), cur_dir.join("Cargo.toml"));
+        assert_eq!(
+            manifest_path(Some(Path::new("crates/a/Cargo.toml"))).unwrap(),
+            cur_dir.join("crates/a/Cargo.toml")
+        );
+        let path = cur_dir.join("Cargo.toml");
+        assert_eq!(manifest_path(Some(&path)).unwrap(), path);
+        for path in ["crates/a", "crates/a/Cargo.lock"] {
+            assert!(
+                matches!(manifest_path(Some(Path::new(path))), Err(Error::Usage(_))),
+                "{}",
+                path
+            );
+        }
     }
 }
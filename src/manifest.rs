@@ -0,0 +1,353 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use cargo_toml::Package;
+use serde::Serialize;
+use toml_edit::{DocumentMut, ImDocument, InlineTable, Item, Table, TableLike, Value};
+
+use crate::error::{Error, Result};
+
+/// A `Cargo.toml` file that is edited in place.
+///
+/// Only the keys whose values actually change are touched, so comments,
+/// formatting and the order of the keys are preserved.
+pub(crate) struct ManifestFile {
+    path: PathBuf,
+    original: String,
+    document: DocumentMut,
+}
+
+impl ManifestFile {
+    pub(crate) fn read(path: &Path) -> Result<Self> {
+        let original = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        Self::parse(path.to_path_buf(), original)
+    }
+
+    pub(crate) fn parse(path: PathBuf, original: String) -> Result<Self> {
+        let document = original
+            .parse::<DocumentMut>()
+            .map_err(|e| Error::invalid_manifest(&path, e))?;
+        Ok(Self {
+            path,
+            original,
+            document,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn original(&self) -> &str {
+        &self.original
+    }
+
+    pub(crate) fn manifest(&self) -> Result<cargo_toml::Manifest> {
+        toml::from_str(&self.original).map_err(|e| Error::invalid_manifest(&self.path, e))
+    }
+
+    /// Returns the `[package]` section of the manifest.
+    pub(crate) fn package(&self) -> Result<Package> {
+        self.manifest()?
+            .package
+            .ok_or_else(|| Error::invalid_manifest(&self.path, "no `[package]` section"))
+    }
+
+    /// Applies the difference between `old` and `new` to the `[package]` table.
+    pub(crate) fn update_package(&mut self, old: &Package, new: &Package) -> Result<()> {
+        self.update(&["package"], &to_table(old), &to_table(new))
+    }
+
+    /// Applies the difference between `old` and `new` to the table at the
+    /// given path, e.g. `["workspace", "package"]`, creating it if necessary.
+    pub(crate) fn update(
+        &mut self,
+        path: &[&str],
+        old: &toml::Table,
+        new: &toml::Table,
+    ) -> Result<()> {
+        let mut table = self.document.as_table_mut();
+        for key in path {
+            table = table
+                .entry(key)
+                .or_insert_with(|| Item::Table(Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| {
+                    Error::invalid_manifest(&self.path, format!("`{}` is not a table", key))
+                })?;
+        }
+        update_table(table, old, new);
+        Ok(())
+    }
+
+    /// Returns the item at the key of the `[package]` table as it is written in the file.
+    pub(crate) fn package_item(&self, key: &str) -> Option<&Item> {
+        self.document.get("package")?.get(key)
+    }
+
+    /// Makes the fields of `[package]` inherit their values from `[workspace.package]`.
+    ///
+    /// The comments after the replaced values are kept only with `keep_comments`, as they
+    /// may describe a value that is no longer there.
+    pub(crate) fn inherit_package_fields(
+        &mut self,
+        fields: &[&str],
+        keep_comments: bool,
+    ) -> Result<()> {
+        let package = self
+            .document
+            .get_mut("package")
+            .and_then(Item::as_table_mut)
+            .ok_or_else(|| Error::invalid_manifest(&self.path, "`package` is not a table"))?;
+        for field in fields {
+            let inherited = Value::InlineTable(InlineTable::from_iter([("workspace", true)]));
+            if keep_comments {
+                set_value(package, field, inherited);
+            } else {
+                package.insert(field, Item::Value(inherited));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the span of the key in the table at the given path, e.g. `["package"]`,
+    /// or of the header of the table if the key is absent.
+    pub(crate) fn key_span(&self, path: &[&str], key: &str) -> Option<Range<usize>> {
+        // Unlike `DocumentMut`, `ImDocument` keeps the spans of the parsed items.
+        let document = ImDocument::parse(self.original.as_str()).ok()?;
+        let mut table: &dyn TableLike = document.as_table();
+        let mut header = None;
+        for name in path {
+            let item = table.get(name)?;
+            header = item.span();
+            table = item.as_table_like()?;
+        }
+        match table.get_key_value(key) {
+            Some((key, _)) => key.span(),
+            None => header,
+        }
+    }
+
+    /// Returns the span of the value of the key in the table at the given path,
+    /// or of its element at the index if the value is an array.
+    ///
+    /// Falls back to the span of the whole value if the array is shorter.
+    pub(crate) fn value_span(
+        &self,
+        path: &[&str],
+        key: &str,
+        index: Option<usize>,
+    ) -> Option<Range<usize>> {
+        let document = ImDocument::parse(self.original.as_str()).ok()?;
+        let mut table: &dyn TableLike = document.as_table();
+        for name in path {
+            table = table.get(name)?.as_table_like()?;
+        }
+        let value = table.get(key)?.as_value()?;
+        let element = index.and_then(|i| value.as_array()?.get(i));
+        element.unwrap_or(value).span()
+    }
+
+    /// Returns the line of the span, with the span underlined.
+    ///
+    /// Spans over several lines are only underlined up to the end of the first one.
+    pub(crate) fn excerpt(&self, span: Range<usize>) -> Excerpt {
+        let line_start = self.original[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.original[span.start..]
+            .find('\n')
+            .map_or(self.original.len(), |i| span.start + i);
+        let text = self.original[line_start..line_end].trim_end_matches('\r');
+        let start = self.original[line_start..span.start].chars().count();
+        let end = self.original[line_start..span.end.min(line_end)]
+            .chars()
+            .count();
+        Excerpt {
+            line: self.position(span.start).line,
+            text: text.to_string(),
+            underline: start..end.max(start + 1),
+        }
+    }
+
+    /// Converts a byte offset in the file to a 1-based line and column.
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let before = &self.original[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    pub(crate) fn contents(&self) -> String {
+        self.document.to_string()
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        std::fs::write(&self.path, self.contents()).map_err(|e| Error::io(&self.path, e))
+    }
+}
+
+/// A position in a file, as shown by editors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Position {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self { line: 1, column: 1 }
+    }
+}
+
+/// A line of a file with a part of it underlined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Excerpt {
+    /// The 1-based line number.
+    pub(crate) line: usize,
+    pub(crate) text: String,
+    /// The 0-based range of the underlined characters.
+    pub(crate) underline: Range<usize>,
+}
+
+/// Serializes the value, e.g. a [`Package`], to a TOML table.
+///
+/// The manifest types always serialize to tables, so this never fails for them.
+pub(crate) fn to_table<T: Serialize>(value: &T) -> toml::Table {
+    toml::Table::try_from(value).expect("the value doesn't serialize to a TOML table")
+}
+
+/// Returns the value of the field as it would be written to `Cargo.toml`
+/// or `None` if the field is unset.
+pub(crate) fn package_field(package: &Package, field: &str) -> Option<toml::Value> {
+    to_table(package).remove(field)
+}
+
+/// Whether the value is `{ workspace = true }`.
+pub(crate) fn is_inherited_value(value: &toml::Value) -> bool {
+    value.get("workspace").and_then(toml::Value::as_bool) == Some(true)
+}
+
+/// Writes the keys of `new` that differ from `old` into `table` and removes
+/// the keys that are absent from `new`.
+///
+/// The decor (whitespace and comments) of the replaced values is kept.
+pub(crate) fn update_table(table: &mut Table, old: &toml::Table, new: &toml::Table) {
+    for (key, value) in new {
+        if old.get(key) == Some(value) {
+            continue;
+        }
+        let value = value
+            .serialize(toml_edit::ser::ValueSerializer::new())
+            .expect("a TOML value always serializes to a TOML value");
+        set_value(table, key, value);
+    }
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            table.remove(key);
+        }
+    }
+}
+
+/// Sets the value of the key, keeping the decor of the value it replaces.
+fn set_value(table: &mut Table, key: &str, mut value: Value) {
+    match table.get_mut(key) {
+        Some(Item::Value(existing)) => {
+            *value.decor_mut() = existing.decor().clone();
+            *existing = value;
+        }
+        _ => {
+            table.insert(key, Item::Value(value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_toml::Inheritable;
+
+    use super::*;
+
+    const MANIFEST: &str = r#"# The package section.
+[package]
+name = "example"   # the name
+version = "0.1.0"
+edition = "2021"
+license = "MIT" # pinned by legal
+
+[dependencies]
+# Pinned because of a regression in 1.0.1.
+serde = { version = "=1.0.0", features = ["derive"] }
+"#;
+
+    fn file() -> ManifestFile {
+        ManifestFile::parse(PathBuf::from("Cargo.toml"), MANIFEST.to_string()).unwrap()
+    }
+
+    #[test]
+    fn untouched_manifest_is_byte_identical() {
+        let mut file = file();
+        let package = file.package().unwrap();
+        file.update_package(&package, &package.clone()).unwrap();
+        assert_eq!(file.contents(), MANIFEST);
+    }
+
+    #[test]
+    fn only_changed_keys_are_edited() {
+        let mut file = file();
+        let old = file.package().unwrap();
+        let mut new = old.clone();
+        new.license = Some(Inheritable::Set("MIT OR Apache-2.0".to_string()));
+        new.description = Some(Inheritable::Set("An example.".to_string()));
+        file.update_package(&old, &new).unwrap();
+        assert_eq!(
+            file.contents(),
+            MANIFEST
+                .replace(
+                    r#"license = "MIT" # pinned"#,
+                    r#"license = "MIT OR Apache-2.0" # pinned"#
+                )
+                .replace(
+                    "# pinned by legal\n",
+                    "# pinned by legal\ndescription = \"An example.\"\n"
+                )
+        );
+    }
+
+    #[test]
+    fn removed_keys_are_dropped() {
+        let mut file = file();
+        let old = file.package().unwrap();
+        let mut new = old.clone();
+        new.license = None;
+        file.update_package(&old, &new).unwrap();
+        assert_eq!(
+            file.contents(),
+            MANIFEST.replace("license = \"MIT\" # pinned by legal\n", "")
+        );
+    }
+
+    #[test]
+    fn keys_are_located() {
+        let file = file();
+        let position = |key| file.position(file.key_span(&["package"], key).unwrap().start);
+        assert_eq!(position("license"), Position { line: 6, column: 1 });
+        // Missing keys point to the table header.
+        assert_eq!(position("description"), Position { line: 2, column: 1 });
+    }
+
+    #[test]
+    fn values_are_underlined() {
+        let file = file();
+        let span = file.value_span(&["package"], "license", None).unwrap();
+        let excerpt = file.excerpt(span);
+        assert_eq!(excerpt.line, 6);
+        assert_eq!(excerpt.text, "license = \"MIT\" # pinned by legal");
+        assert_eq!(excerpt.underline, 10..15);
+
+        let span = file
+            .value_span(&["dependencies", "serde"], "features", Some(0))
+            .unwrap();
+        assert_eq!(file.excerpt(span).underline, 42..50);
+    }
+}
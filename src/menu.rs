@@ -0,0 +1,150 @@
+use promptly::prompt;
+
+use crate::answers::Answer;
+use crate::config::FieldDefault;
+use crate::error::Result;
+
+/// What choosing an option of a menu does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Choice {
+    /// Fill the field as if the answer was given in advance.
+    Answer(Answer),
+    /// Enter the value manually.
+    Manual,
+}
+
+/// A numbered list of the methods of entering a field.
+pub(crate) struct Menu {
+    question: String,
+    options: Vec<(String, Choice)>,
+}
+
+impl Menu {
+    pub(crate) fn new(question: impl Into<String>) -> Self {
+        Self {
+            question: question.into(),
+            options: Vec::new(),
+        }
+    }
+
+    pub(crate) fn option(mut self, text: impl Into<String>, choice: Choice) -> Self {
+        self.options.push((text.into(), choice));
+        self
+    }
+
+    /// Makes the default of the field the first option.
+    ///
+    /// If the menu already has an option for the default, it is moved to the top.
+    pub(crate) fn with_default(mut self, default: Option<FieldDefault>) -> Self {
+        let Some(FieldDefault { answer, label }) = default else {
+            return self;
+        };
+        let text = match &answer {
+            Answer::Value(value) => format!("Default to `{}`.", value),
+            Answer::Skip => "Skip.".to_string(),
+            strategy => format!("Use `{}`.", strategy),
+        };
+        let choice = Choice::Answer(answer);
+        let text = match self.options.iter().position(|(_, c)| *c == choice) {
+            Some(i) => self.options.remove(i).0,
+            None => text,
+        };
+        let text = match label {
+            Some(label) => format!("{} ({}).", text.trim_end_matches('.'), label),
+            None => text,
+        };
+        self.options.insert(0, (text, choice));
+        self
+    }
+
+    /// Removes the option to skip the field if the field is required.
+    pub(crate) fn required(mut self, required: bool) -> Self {
+        if required {
+            self.options
+                .retain(|(_, choice)| *choice != Choice::Answer(Answer::Skip));
+        }
+        self
+    }
+
+    /// Asks to choose an option until a valid one is chosen.
+    pub(crate) fn choose(&self) -> Result<Choice> {
+        let msg = self.message();
+        loop {
+            let c: String = prompt(&msg)?;
+            match self.parse(&c) {
+                Some(choice) => return Ok(choice.clone()),
+                None => println!("Invalid input."),
+            }
+        }
+    }
+
+    /// The question followed by the numbered options.
+    fn message(&self) -> String {
+        let mut msg = format!("{}\n\n", self.question);
+        for (i, (text, _)) in self.options.iter().enumerate() {
+            msg.push_str(&format!("{}. {}\n", i + 1, text));
+        }
+        msg
+    }
+
+    /// Returns the option with the given number, counting from 1.
+    fn parse(&self, input: &str) -> Option<&Choice> {
+        let i = input.trim().parse::<usize>().ok()?.checked_sub(1)?;
+        self.options.get(i).map(|(_, choice)| choice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn documentation_menu() -> Menu {
+        Menu::new("Please choose:")
+            .option("Skip.", Choice::Answer(Answer::Skip))
+            .option("Use `docs-rs`.", Choice::Answer(Answer::DocsRs))
+            .option("Enter the value manually.", Choice::Manual)
+    }
+
+    #[test]
+    fn options_are_chosen_by_number() {
+        let menu = documentation_menu();
+        assert_eq!(menu.parse("1"), Some(&Choice::Answer(Answer::Skip)));
+        assert_eq!(menu.parse(" 3 "), Some(&Choice::Manual));
+        for invalid in ["0", "4", "-1", "", "two"] {
+            assert_eq!(menu.parse(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn the_default_is_moved_to_the_top() {
+        let default = |answer, label: Option<&str>| {
+            Some(FieldDefault {
+                answer,
+                label: label.map(str::to_string),
+            })
+        };
+        let menu = documentation_menu().with_default(default(Answer::DocsRs, Some("recommended")));
+        assert_eq!(
+            menu.message(),
+            "Please choose:\n\n\
+             1. Use `docs-rs` (recommended).\n\
+             2. Skip.\n\
+             3. Enter the value manually.\n"
+        );
+
+        let menu = documentation_menu().with_default(default(Answer::Value("x".to_string()), None));
+        assert_eq!(
+            menu.parse("1"),
+            Some(&Choice::Answer(Answer::Value("x".to_string())))
+        );
+        assert_eq!(menu.options.len(), 4);
+    }
+
+    #[test]
+    fn required_fields_cannot_be_skipped() {
+        let menu = documentation_menu().required(true);
+        assert_eq!(menu.parse("1"), Some(&Choice::Answer(Answer::DocsRs)));
+        assert_eq!(menu.parse("3"), None);
+        assert_eq!(documentation_menu().required(false).options.len(), 3);
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use cargo_toml::{Inheritable, Publish};
+use serde::Deserialize;
+use spdx::Expression;
+
+use crate::answers::{Answer, RawAnswer, FIELDS};
+use crate::error::{Error, Result};
+use crate::license::normalize_license;
+use crate::manifest::ManifestFile;
+use crate::validate::Severity;
+
+/// The name of the policy files.
+const POLICY_FILE: &str = ".cargo-fill.toml";
+
+/// A policy file as it is written.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct RawPolicy {
+    required: Option<Vec<String>>,
+    allowed_licenses: Option<Vec<String>>,
+    allowed_dependency_licenses: Option<Vec<String>>,
+    repository_prefix: Option<String>,
+    publish: Option<Vec<String>>,
+    never_ask: Option<Vec<String>>,
+    reuse: Option<bool>,
+    #[serde(default)]
+    fixed: BTreeMap<String, RawAnswer>,
+    #[serde(default)]
+    severity: BTreeMap<String, Severity>,
+}
+
+impl RawPolicy {
+    /// Overrides the settings with the ones given in the nested policy.
+    fn merge(&mut self, nested: RawPolicy) {
+        self.required = nested.required.or(self.required.take());
+        self.allowed_licenses = nested.allowed_licenses.or(self.allowed_licenses.take());
+        self.allowed_dependency_licenses = nested
+            .allowed_dependency_licenses
+            .or(self.allowed_dependency_licenses.take());
+        self.repository_prefix = nested.repository_prefix.or(self.repository_prefix.take());
+        self.publish = nested.publish.or(self.publish.take());
+        self.never_ask = nested.never_ask.or(self.never_ask.take());
+        self.reuse = nested.reuse.or(self.reuse);
+        self.fixed.extend(nested.fixed);
+        self.severity.extend(nested.severity);
+    }
+}
+
+/// The rules of a team or an organization for the manifests, e.g.
+///
+/// ```toml
+/// required = ["description", "license", "repository"]
+/// allowed-licenses = ["MIT", "Apache-2.0", "MIT OR Apache-2.0"]
+/// allowed-dependency-licenses = ["MIT", "Apache-2.0", "BSD-3-Clause", "ISC", "Unicode-3.0"]
+/// repository-prefix = "https://github.com/acme/"
+/// publish = ["acme"]
+/// never-ask = ["homepage"]
+/// reuse = true
+///
+/// [fixed]
+/// authors = ["Acme Developers <dev@acme.com>"]
+///
+/// [severity]
+/// repository = "error"
+/// ```
+#[derive(Debug, Default)]
+pub(crate) struct Policy {
+    required: Vec<String>,
+    allowed_licenses: Option<Vec<String>>,
+    allowed_dependency_licenses: Option<Vec<String>>,
+    repository_prefix: Option<String>,
+    publish: Option<Vec<String>>,
+    never_ask: Vec<String>,
+    reuse: bool,
+    fixed: BTreeMap<String, Answer>,
+    severity: BTreeMap<String, Severity>,
+}
+
+impl Policy {
+    /// Reads the `.cargo-fill.toml` files in the directory and its parents,
+    /// up to the root of the git repository.
+    ///
+    /// The settings of the files closer to the directory override the ones further away.
+    pub(crate) fn find(dir: &Path) -> Result<Self> {
+        let mut paths = Vec::new();
+        for dir in dir.ancestors() {
+            let path = dir.join(POLICY_FILE);
+            if path.is_file() {
+                paths.push(path);
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+        let mut raw = RawPolicy::default();
+        for path in paths.iter().rev() {
+            raw.merge(Self::read(path)?);
+        }
+        Self::validate(raw)
+    }
+
+    fn read(path: &Path) -> Result<RawPolicy> {
+        let contents = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::Usage(format!("invalid policy file {}: {}", path.display(), e)))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_toml(contents: &str) -> Result<Self> {
+        Self::validate(toml::from_str(contents).expect("invalid policy"))
+    }
+
+    fn validate(raw: RawPolicy) -> Result<Self> {
+        let invalid = |e: &dyn std::fmt::Display| Error::Usage(format!("invalid policy: {}", e));
+        let required = raw.required.unwrap_or_default();
+        let never_ask = raw.never_ask.unwrap_or_default();
+        for field in required.iter().chain(&never_ask).chain(raw.severity.keys()) {
+            if !FIELDS.contains(&field.as_str()) {
+                return Err(invalid(&format!("unknown field `{}`", field)));
+            }
+        }
+        for license in raw.allowed_dependency_licenses.iter().flatten() {
+            if spdx::license_id(license).is_none() {
+                return Err(invalid(&format!(
+                    "unknown license `{}` in `allowed-dependency-licenses`",
+                    license
+                )));
+            }
+        }
+        let allowed_licenses = match raw.allowed_licenses {
+            Some(licenses) => Some(
+                licenses
+                    .iter()
+                    .map(|license| normalize_license(license))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| invalid(&format!("in `allowed-licenses`: {}", e)))?,
+            ),
+            None => None,
+        };
+        let mut fixed = BTreeMap::new();
+        for (field, answer) in raw.fixed {
+            let answer = answer.into_answer(&field).map_err(|e| invalid(&e))?;
+            fixed.insert(field, answer);
+        }
+        Ok(Self {
+            required,
+            allowed_licenses,
+            allowed_dependency_licenses: raw.allowed_dependency_licenses,
+            repository_prefix: raw.repository_prefix,
+            publish: raw.publish,
+            never_ask,
+            reuse: raw.reuse.unwrap_or_default(),
+            fixed,
+            severity: raw.severity,
+        })
+    }
+
+    /// The value that the field must have, if any.
+    pub(crate) fn fixed(&self, field: &str) -> Option<&Answer> {
+        self.fixed.get(field)
+    }
+
+    pub(crate) fn required(&self) -> &[String] {
+        &self.required
+    }
+
+    pub(crate) fn is_required(&self, field: &str) -> bool {
+        self.required.iter().any(|required| required == field)
+    }
+
+    /// Whether the field must not be prompted for.
+    pub(crate) fn never_asks(&self, field: &str) -> bool {
+        self.never_ask.iter().any(|never_ask| never_ask == field)
+    }
+
+    /// The severity of the findings for the field in `cargo fill check`, if overridden.
+    pub(crate) fn severity(&self, field: &str) -> Option<Severity> {
+        self.severity.get(field).copied()
+    }
+
+    /// Whether the packages follow the REUSE specification, with the license texts
+    /// in `LICENSES` and SPDX headers in the source files.
+    pub(crate) fn reuse(&self) -> bool {
+        self.reuse
+    }
+
+    /// The only registries that the packages may be published to, if restricted.
+    pub(crate) fn publish(&self) -> Option<&[String]> {
+        self.publish.as_deref()
+    }
+
+    /// The licenses that the packages may use, if restricted.
+    pub(crate) fn allowed_licenses(&self) -> &[String] {
+        self.allowed_licenses.as_deref().unwrap_or_default()
+    }
+
+    /// The SPDX identifiers of the licenses that the dependencies may use, if restricted.
+    pub(crate) fn allowed_dependency_licenses(&self) -> Option<&[String]> {
+        self.allowed_dependency_licenses.as_deref()
+    }
+
+    pub(crate) fn repository_prefix(&self) -> Option<&str> {
+        self.repository_prefix.as_deref()
+    }
+
+    /// Fails unless the license can be satisfied with only the licenses in `allowed-licenses`,
+    /// e.g. `Apache-2.0 OR MIT` when `MIT OR Apache-2.0` or just `MIT` is allowed.
+    pub(crate) fn check_license(&self, license: &str) -> Result<()> {
+        let Some(allowed) = &self.allowed_licenses else {
+            return Ok(());
+        };
+        // The allowed licenses are normalized when the policy is loaded.
+        let terms: Vec<String> = allowed
+            .iter()
+            .filter_map(|allowed| Expression::parse(allowed).ok())
+            .flat_map(|allowed| {
+                allowed
+                    .requirements()
+                    .map(|requirement| requirement.req.license.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let is_allowed = normalize_license(license)
+            .ok()
+            .and_then(|license| Expression::parse(&license).ok())
+            .is_some_and(|expression| {
+                expression.evaluate(|req| terms.contains(&req.license.to_string()))
+            });
+        if is_allowed {
+            return Ok(());
+        }
+        Err(Error::Policy(format!(
+            "the license `{}` is not allowed, use one of: {}",
+            license,
+            allowed.join(", ")
+        )))
+    }
+
+    pub(crate) fn check_repository(&self, repository: &str) -> Result<()> {
+        match &self.repository_prefix {
+            Some(prefix) if !repository.starts_with(prefix.as_str()) => Err(Error::Policy(
+                format!("the repository URL must start with `{}`", prefix),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if the package can be published to a registry that the policy doesn't list.
+    ///
+    /// Private packages, i.e. `publish = false`, and inherited values are always accepted.
+    pub(crate) fn check_publish(&self, publish: &Inheritable<Publish>) -> Result<()> {
+        let Some(allowed) = &self.publish else {
+            return Ok(());
+        };
+        let disallowed = match publish {
+            Inheritable::Inherited | Inheritable::Set(Publish::Flag(false)) => return Ok(()),
+            Inheritable::Set(Publish::Flag(true)) => vec!["any registry"],
+            Inheritable::Set(Publish::Registry(registries)) => registries
+                .iter()
+                .filter(|registry| !allowed.contains(registry))
+                .map(String::as_str)
+                .collect(),
+        };
+        if disallowed.is_empty() {
+            return Ok(());
+        }
+        Err(Error::Policy(format!(
+            "the package can be published to {}, but the policy only allows: {}",
+            disallowed.join(", "),
+            allowed.join(", ")
+        )))
+    }
+
+    /// Fails if the manifest lacks any of the required fields.
+    ///
+    /// `Package` has defaults for some fields, e.g. `edition`, so the manifest itself is checked.
+    pub(crate) fn check_required(&self, file: &ManifestFile) -> Result<()> {
+        let missing: Vec<&str> = self
+            .required
+            .iter()
+            .map(String::as_str)
+            .filter(|field| file.package_item(field).is_none())
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(Error::Policy(format!(
+            "the policy requires the fields that are missing from {}: {}",
+            file.path().display(),
+            missing.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(contents: &str) -> Policy {
+        Policy::from_toml(contents).unwrap()
+    }
+
+    #[test]
+    fn licenses_are_compared_as_expressions() {
+        let dual = policy(r#"allowed-licenses = ["MIT OR Apache-2.0"]"#);
+        assert!(dual.check_license("MIT OR Apache-2.0").is_ok());
+        assert!(dual.check_license("Apache-2.0 OR MIT").is_ok());
+        assert!(dual.check_license("MIT/Apache-2.0").is_ok());
+        assert!(dual.check_license("GPL-3.0-only").is_err());
+
+        let single = policy(r#"allowed-licenses = ["MIT", "Apache-2.0"]"#);
+        assert!(single.check_license("MIT OR Apache-2.0").is_ok());
+        assert!(single.check_license("MIT AND Apache-2.0").is_ok());
+        // Any of the licenses can be chosen, so one allowed license is enough.
+        assert!(single.check_license("GPL-3.0-only OR MIT").is_ok());
+        assert!(single.check_license("GPL-3.0-only AND MIT").is_err());
+        assert!(single.check_license("not a license").is_err());
+
+        assert!(Policy::from_toml(r#"allowed-licenses = ["MTI"]"#).is_err());
+    }
+
+    #[test]
+    fn publishing_is_limited_to_the_registries() {
+        let publish = |publish| policy(r#"publish = ["acme", "mirror"]"#).check_publish(&publish);
+        let registries = |names: &[&str]| {
+            Inheritable::Set(Publish::Registry(
+                names.iter().map(|n| n.to_string()).collect(),
+            ))
+        };
+        assert!(publish(Inheritable::Set(Publish::Flag(false))).is_ok());
+        assert!(publish(Inheritable::Inherited).is_ok());
+        assert!(publish(registries(&["acme"])).is_ok());
+        assert!(publish(registries(&["acme", "crates-io"])).is_err());
+        assert!(publish(Inheritable::Set(Publish::Flag(true))).is_err());
+        assert!(policy("")
+            .check_publish(&Inheritable::Set(Publish::Flag(true)))
+            .is_ok());
+    }
+
+    #[test]
+    fn required_fields_are_looked_up_in_the_manifest() {
+        let file = ManifestFile::parse(
+            "Cargo.toml".into(),
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nlicense = \"MIT\"\n".to_string(),
+        )
+        .unwrap();
+        assert!(policy(r#"required = ["license"]"#)
+            .check_required(&file)
+            .is_ok());
+        // `Package` defaults `edition` to 2015, but the manifest doesn't set it.
+        let e = policy(r#"required = ["license", "edition"]"#)
+            .check_required(&file)
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "policy violation: the policy requires the fields that are missing from Cargo.toml: edition"
+        );
+    }
+
+    #[test]
+    fn nested_policies_take_precedence() {
+        let mut raw: RawPolicy = toml::from_str(
+            r#"
+            required = ["license"]
+            allowed-licenses = ["MIT"]
+            repository-prefix = "https://github.com/acme/"
+
+            [fixed]
+            authors = ["Acme"]
+            edition = "2021"
+            "#,
+        )
+        .unwrap();
+        raw.merge(
+            toml::from_str(
+                r#"
+                allowed-licenses = ["Apache-2.0"]
+                never-ask = ["homepage"]
+
+                [fixed]
+                edition = "2024"
+                "#,
+            )
+            .unwrap(),
+        );
+        let policy = Policy::validate(raw).unwrap();
+        assert_eq!(policy.required(), ["license"]);
+        assert_eq!(policy.allowed_licenses(), ["Apache-2.0"]);
+        assert_eq!(policy.repository_prefix(), Some("https://github.com/acme/"));
+        assert!(policy.never_asks("homepage"));
+        assert!(policy.fixed("authors").is_some());
+        assert_eq!(
+            policy.fixed("edition"),
+            Some(&Answer::Value("2024".to_string()))
+        );
+    }
+}
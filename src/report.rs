@@ -0,0 +1,483 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::diff;
+use crate::manifest::{Excerpt, ManifestFile, Position};
+use crate::validate::{Finding, Severity, Target, CHECKED_FIELDS};
+
+/// The output formats of `cargo fill check`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Human-readable text.
+    Human,
+    /// GitHub Actions workflow commands, shown as annotations on pull requests.
+    Github,
+    /// A SARIF 2.1.0 log for code scanning.
+    Sarif,
+    /// JUnit XML for test dashboards.
+    Junit,
+}
+
+/// A finding located in a manifest.
+#[derive(Debug)]
+pub(crate) struct Diagnostic {
+    pub(crate) path: PathBuf,
+    /// The position of the offending key or value or, if the key is missing, of the table header.
+    pub(crate) position: Position,
+    /// The line of the position with the offending part underlined.
+    pub(crate) excerpt: Option<Excerpt>,
+    pub(crate) finding: Finding,
+}
+
+impl Diagnostic {
+    /// Locates the finding in the table at the given path of the manifest, e.g. `["package"]`.
+    pub(crate) fn locate(file: &ManifestFile, table: &[&str], finding: Finding) -> Self {
+        let field = finding.field.as_str();
+        let value_span = match finding.target {
+            Target::Key => None,
+            Target::Value => file.value_span(table, field, None),
+            Target::Element(i) => file.value_span(table, field, Some(i)),
+        };
+        let span = value_span.or_else(|| file.key_span(table, field));
+        Self {
+            path: file.path().to_path_buf(),
+            position: span
+                .as_ref()
+                .map(|span| file.position(span.start))
+                .unwrap_or_default(),
+            excerpt: span.map(|span| file.excerpt(span)),
+            finding,
+        }
+    }
+
+    pub(crate) fn is_error(&self) -> bool {
+        self.finding.severity == Severity::Error
+    }
+
+    /// The message followed by the suggestion, if any.
+    fn message_with_help(&self) -> String {
+        match &self.finding.help {
+            Some(help) => format!("{}\nhelp: {}", self.finding.message, help),
+            None => self.finding.message.clone(),
+        }
+    }
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Allow => "allow",
+    }
+}
+
+/// Returns the path relative to the current directory, with forward slashes,
+/// as expected by GitHub and SARIF viewers.
+fn relative_path(path: &Path) -> String {
+    let path = std::env::current_dir()
+        .ok()
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path);
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Renders the diagnostics for the checked manifests in the format.
+///
+/// Only the human format is colored, if `color` is set.
+pub(crate) fn render(
+    format: Format,
+    checked: &[PathBuf],
+    diagnostics: &[Diagnostic],
+    color: bool,
+) -> String {
+    match format {
+        Format::Human => human(checked, diagnostics, color),
+        Format::Github => github(diagnostics),
+        Format::Sarif => sarif(diagnostics),
+        Format::Junit => junit(checked, diagnostics),
+    }
+}
+
+fn summary(diagnostics: &[Diagnostic]) -> String {
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+    if diagnostics.is_empty() {
+        "The metadata is complete.\n".to_string()
+    } else {
+        format!("Found {} error(s) and {} warning(s).\n", errors, warnings)
+    }
+}
+
+/// Renders the diagnostics like rustc, with the offending part of the manifest underlined.
+fn human(checked: &[PathBuf], diagnostics: &[Diagnostic], color: bool) -> String {
+    let (red, yellow, blue, bold, reset) = if color {
+        (diff::RED, diff::YELLOW, diff::BLUE, diff::BOLD, diff::RESET)
+    } else {
+        ("", "", "", "", "")
+    };
+    let mut out = String::new();
+    for path in checked {
+        writeln!(out, "Checking {}.", path.display()).unwrap();
+        for d in diagnostics.iter().filter(|d| d.path == *path) {
+            let severity_color = if d.is_error() { red } else { yellow };
+            writeln!(
+                out,
+                "{bold}{severity_color}{}{reset}{bold}: {}{reset}",
+                severity_name(d.finding.severity),
+                d.finding.message
+            )
+            .unwrap();
+            let gutter = d.excerpt.as_ref().map_or(0, |e| e.line.to_string().len());
+            let indent = " ".repeat(gutter);
+            writeln!(
+                out,
+                "{indent}{blue}{bold}-->{reset} {}:{}:{}",
+                path.display(),
+                d.position.line,
+                d.position.column
+            )
+            .unwrap();
+            if let Some(excerpt) = &d.excerpt {
+                let padding = " ".repeat(excerpt.underline.start);
+                let carets = "^".repeat(excerpt.underline.len());
+                writeln!(out, "{indent} {blue}{bold}|{reset}").unwrap();
+                writeln!(
+                    out,
+                    "{blue}{bold}{} |{reset} {}",
+                    excerpt.line, excerpt.text
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "{indent} {blue}{bold}|{reset} {padding}{bold}{severity_color}{carets}{reset}"
+                )
+                .unwrap();
+            }
+            if let Some(help) = &d.finding.help {
+                writeln!(
+                    out,
+                    "{indent} {blue}{bold}={reset} {bold}help{reset}: {}",
+                    help
+                )
+                .unwrap();
+            }
+            out.push('\n');
+        }
+        if !diagnostics.iter().any(|d| d.path == *path) {
+            out.push('\n');
+        }
+    }
+    out.push_str(&summary(diagnostics));
+    out
+}
+
+/// Escapes the data of a workflow command.
+fn github_escape(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes the property of a workflow command.
+fn github_escape_property(s: &str) -> String {
+    github_escape(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn github(diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::new();
+    for d in diagnostics {
+        writeln!(
+            out,
+            "::{} file={},line={},col={},title={}::{}",
+            severity_name(d.finding.severity),
+            github_escape_property(&relative_path(&d.path)),
+            d.position.line,
+            d.position.column,
+            github_escape_property(&format!("cargo fill: {}", d.finding.field)),
+            github_escape(&d.message_with_help())
+        )
+        .unwrap();
+    }
+    out.push_str(&summary(diagnostics));
+    out
+}
+
+fn sarif(diagnostics: &[Diagnostic]) -> String {
+    let rules: BTreeSet<&str> = diagnostics
+        .iter()
+        .map(|d| d.finding.field.as_str())
+        .collect();
+    let rules: Vec<_> = rules
+        .into_iter()
+        .map(|field| {
+            serde_json::json!({
+                "id": field,
+                "shortDescription": { "text": format!("The `{}` field of Cargo.toml", field) },
+            })
+        })
+        .collect();
+    let results: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            serde_json::json!({
+                "ruleId": d.finding.field,
+                "level": severity_name(d.finding.severity),
+                "message": { "text": d.message_with_help() },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": relative_path(&d.path) },
+                        "region": {
+                            "startLine": d.position.line,
+                            "startColumn": d.position.column,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+    let log = serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cargo-fill",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+    let mut out = serde_json::to_string_pretty(&log).expect("the SARIF log always serializes");
+    out.push('\n');
+    out
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Renders a test suite per manifest and a test case per field.
+///
+/// The test cases with errors fail, and the warnings are reported in the output of the test cases.
+fn junit(checked: &[PathBuf], diagnostics: &[Diagnostic]) -> String {
+    let mut paths: Vec<&PathBuf> = checked.iter().collect();
+    for d in diagnostics {
+        if !paths.contains(&&d.path) {
+            paths.push(&d.path);
+        }
+    }
+    let mut suites = String::new();
+    let (mut total, mut failures) = (0, 0);
+    for path in paths {
+        let path_diagnostics: Vec<&Diagnostic> =
+            diagnostics.iter().filter(|d| d.path == *path).collect();
+        let mut fields: Vec<&str> = CHECKED_FIELDS.to_vec();
+        for d in &path_diagnostics {
+            if !fields.contains(&d.finding.field.as_str()) {
+                fields.push(&d.finding.field);
+            }
+        }
+        let name = xml_escape(&relative_path(path));
+        // A test case fails if any of the diagnostics of its field is an error.
+        let cases: Vec<(&str, Vec<&Diagnostic>)> = fields
+            .into_iter()
+            .map(|field| {
+                let ds = path_diagnostics
+                    .iter()
+                    .copied()
+                    .filter(|d| d.finding.field == field)
+                    .collect();
+                (field, ds)
+            })
+            .collect();
+        let suite_failures = cases
+            .iter()
+            .filter(|(_, ds)| ds.iter().any(|d| d.is_error()))
+            .count();
+        total += cases.len();
+        failures += suite_failures;
+        writeln!(
+            suites,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+            name,
+            cases.len(),
+            suite_failures
+        )
+        .unwrap();
+        for (field, ds) in cases {
+            write!(
+                suites,
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                xml_escape(field),
+                name
+            )
+            .unwrap();
+            if ds.is_empty() {
+                suites.push_str("/>\n");
+                continue;
+            }
+            suites.push_str(">\n");
+            let line = |d: &Diagnostic| {
+                format!(
+                    "{}: {} ({}:{}:{})",
+                    severity_name(d.finding.severity),
+                    d.message_with_help(),
+                    name,
+                    d.position.line,
+                    d.position.column
+                )
+            };
+            let (errors, warnings): (Vec<&Diagnostic>, Vec<&Diagnostic>) =
+                ds.into_iter().partition(|d| d.is_error());
+            if !errors.is_empty() {
+                let messages: Vec<String> =
+                    errors.iter().map(|d| d.finding.message.clone()).collect();
+                let lines: Vec<String> = errors.iter().map(|d| line(d)).collect();
+                writeln!(
+                    suites,
+                    "      <failure message=\"{}\" type=\"error\">{}</failure>",
+                    xml_escape(&messages.join("; ")),
+                    xml_escape(&lines.join("\n"))
+                )
+                .unwrap();
+            }
+            if !warnings.is_empty() {
+                let lines: Vec<String> = warnings.iter().map(|d| line(d)).collect();
+                writeln!(
+                    suites,
+                    "      <system-out>{}</system-out>",
+                    xml_escape(&lines.join("\n"))
+                )
+                .unwrap();
+            }
+            suites.push_str("    </testcase>\n");
+        }
+        suites.push_str("  </testsuite>\n");
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <testsuites name=\"cargo fill check\" tests=\"{}\" failures=\"{}\">\n{}</testsuites>\n",
+        total, failures, suites
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "example"
+version = "0.1.0"
+license = "MIT/Apache-2.0"
+"#;
+
+    /// A warning and an error for `license` and an error for the missing `description`.
+    fn diagnostics() -> Vec<Diagnostic> {
+        let file = ManifestFile::parse(PathBuf::from("Cargo.toml"), MANIFEST.to_string()).unwrap();
+        [
+            Finding::new("license", Severity::Warning, "a legacy expression")
+                .at(Target::Value)
+                .help("use `MIT OR Apache-2.0`"),
+            Finding::new(
+                "license",
+                Severity::Error,
+                "`gpl 1.0.0` is licensed under `GPL-3.0-only`",
+            )
+            .at(Target::Value),
+            Finding::new(
+                "description",
+                Severity::Error,
+                "the `description` field is missing",
+            ),
+        ]
+        .into_iter()
+        .map(|finding| Diagnostic::locate(&file, &["package"], finding))
+        .collect()
+    }
+
+    #[test]
+    fn human_output_underlines_the_values() {
+        let out = render(
+            Format::Human,
+            &[PathBuf::from("Cargo.toml")],
+            &diagnostics(),
+            false,
+        );
+        assert!(out.starts_with(
+            "Checking Cargo.toml.\n\
+            warning: a legacy expression\n \
+            --> Cargo.toml:4:11\n  \
+            |\n\
+            4 | license = \"MIT/Apache-2.0\"\n  \
+            |           ^^^^^^^^^^^^^^^^\n  \
+            = help: use `MIT OR Apache-2.0`\n"
+        ));
+        assert!(out.ends_with("Found 2 error(s) and 1 warning(s).\n"));
+    }
+
+    #[test]
+    fn github_commands_are_escaped() {
+        assert_eq!(github_escape("50%\nmore"), "50%25%0Amore");
+        assert_eq!(github_escape_property("a: b, c"), "a%3A b%2C c");
+        let out = render(Format::Github, &[], &diagnostics(), false);
+        let first = out.lines().next().unwrap();
+        assert_eq!(
+            first,
+            "::warning file=Cargo.toml,line=4,col=11,title=cargo fill%3A license::\
+            a legacy expression%0Ahelp: use `MIT OR Apache-2.0`"
+        );
+    }
+
+    #[test]
+    fn sarif_log_has_a_result_per_diagnostic() {
+        let out = render(Format::Sarif, &[], &diagnostics(), false);
+        let log: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        let rules: Vec<&str> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(rules, ["description", "license"]);
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1]["level"], "error");
+        let location = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "Cargo.toml");
+        assert_eq!(location["region"]["startLine"], 4);
+    }
+
+    #[test]
+    fn junit_cases_fail_on_any_error() {
+        let out = render(
+            Format::Junit,
+            &[PathBuf::from("Cargo.toml")],
+            &diagnostics(),
+            false,
+        );
+        assert!(out.contains(&format!(
+            "<testsuite name=\"Cargo.toml\" tests=\"{}\" failures=\"2\">",
+            CHECKED_FIELDS.len()
+        )));
+        // The warning comes first, but the error still fails the test case.
+        assert!(out.contains(
+            "    <testcase name=\"license\" classname=\"Cargo.toml\">\n      \
+            <failure message=\"`gpl 1.0.0` is licensed under `GPL-3.0-only`\" type=\"error\">\
+            error: `gpl 1.0.0` is licensed under `GPL-3.0-only` (Cargo.toml:4:11)</failure>\n      \
+            <system-out>warning: a legacy expression\nhelp: use `MIT OR Apache-2.0` \
+            (Cargo.toml:4:11)</system-out>\n    \
+            </testcase>\n"
+        ));
+        assert!(out.contains("<testcase name=\"repository\" classname=\"Cargo.toml\"/>"));
+    }
+}
@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+
+use crate::context::Context;
+use crate::error::{Error, Result};
+use crate::license::is_same_license;
+use crate::policy::Policy;
+
+/// The directory with the license texts of a REUSE-compliant project.
+pub(crate) const LICENSES_DIR: &str = "LICENSES";
+
+/// The tag of the SPDX license headers.
+const HEADER_TAG: &str = "SPDX-License-Identifier:";
+
+/// The number of lines at the start of a file that are searched for the header.
+const HEADER_LINES: usize = 10;
+
+/// Whether the package follows the REUSE specification, i.e. the policy asks for it
+/// or the package already has a `LICENSES` directory.
+pub(crate) fn is_enabled(dir: &Path, policy: &Policy) -> bool {
+    policy.reuse() || dir.join(LICENSES_DIR).is_dir()
+}
+
+/// Returns the path to the text of the license in the `LICENSES` directory, relative to the package.
+pub(crate) fn license_path(id: &str) -> String {
+    format!("{}/{}.txt", LICENSES_DIR, id)
+}
+
+/// A source file whose SPDX header doesn't match the license of the package.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum HeaderIssue {
+    Missing(PathBuf),
+    /// The file declares another license.
+    Mismatch(PathBuf, String),
+}
+
+impl HeaderIssue {
+    pub(crate) fn message(&self) -> String {
+        match self {
+            HeaderIssue::Missing(path) => {
+                format!("`{}` has no SPDX-License-Identifier header", path.display())
+            }
+            HeaderIssue::Mismatch(path, license) => format!(
+                "`{}` has the SPDX-License-Identifier `{}`, which disagrees with `license`",
+                path.display(),
+                license
+            ),
+        }
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            HeaderIssue::Missing(path) | HeaderIssue::Mismatch(path, _) => path,
+        }
+    }
+}
+
+/// Returns the license expression of the SPDX header of the file, if any.
+fn header_license(contents: &str) -> Option<&str> {
+    contents.lines().take(HEADER_LINES).find_map(|line| {
+        let (_, license) = line.split_once(HEADER_TAG)?;
+        Some(license.trim().trim_end_matches("*/").trim_end())
+    })
+}
+
+/// Returns the Rust source files in the directory and its subdirectories, sorted.
+fn rust_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let entries = std::fs::read_dir(dir).map_err(|e| Error::io(dir, e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| Error::io(dir, e))?.path();
+        if path.is_dir() {
+            files.extend(rust_files(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Scans `src/**/*.rs` for the files whose SPDX header is missing or disagrees with the license.
+///
+/// The paths are relative to the package.
+pub(crate) fn scan_headers(dir: &Path, license: &str) -> Result<Vec<HeaderIssue>> {
+    let src = dir.join("src");
+    if !src.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut issues = Vec::new();
+    for path in rust_files(&src)? {
+        let contents = std::fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        let relative = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+        match header_license(&contents) {
+            None => issues.push(HeaderIssue::Missing(relative)),
+            Some(header) if !is_same_license(header, license) => {
+                issues.push(HeaderIssue::Mismatch(relative, header.to_string()));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(issues)
+}
+
+/// Returns the contents with the SPDX header added or replaced.
+///
+/// The rest of the contents is kept byte for byte, including the line endings.
+fn with_header(contents: &str, license: &str) -> String {
+    let header = format!("// {} {}", HEADER_TAG, license);
+    let mut start = 0;
+    for line in contents.split_inclusive('\n').take(HEADER_LINES) {
+        if line.contains(HEADER_TAG) {
+            let end = start + line.trim_end_matches(['\r', '\n']).len();
+            return format!("{}{}{}", &contents[..start], header, &contents[end..]);
+        }
+        start += line.len();
+    }
+    let eol = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    // A shebang must stay on the first line, unlike an inner attribute.
+    if contents.starts_with("#!") && !contents.starts_with("#![") {
+        return match contents.find('\n') {
+            Some(i) => format!("{}{}{}{}", &contents[..=i], header, eol, &contents[i + 1..]),
+            None => format!("{}{}{}", contents, eol, header),
+        };
+    }
+    format!("{}{}{}", header, eol, contents)
+}
+
+/// Reports the source files whose SPDX header is missing or disagrees with the license
+/// and offers to add or update their headers.
+pub(crate) fn offer_headers(license: &str, ctx: &Context) -> Result<()> {
+    let dir = ctx.manifest_dir();
+    let issues = scan_headers(dir, license)?;
+    if issues.is_empty() {
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("warning: {}.", issue.message());
+    }
+    if ctx.is_dry_run() {
+        println!(
+            "Would offer to update the SPDX headers of {} files (dry run).",
+            issues.len()
+        );
+        return Ok(());
+    }
+    if !ctx.confirm(&format!(
+        "Set `SPDX-License-Identifier: {}` in the headers of {} files? (Y/n)",
+        license,
+        issues.len()
+    ))? {
+        return Ok(());
+    }
+    for issue in &issues {
+        let path = dir.join(issue.path());
+        let contents = std::fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        ctx.write_after_save(issue.path(), with_header(&contents, license));
+    }
+    println!(
+        "The SPDX headers of {} files will be updated once the manifest is saved.",
+        issues.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_are_found_and_set() {
+        let file = "// SPDX-License-Identifier: MIT OR Apache-2.0\n\nfn main() {}\n";
+        assert_eq!(header_license(file), Some("MIT OR Apache-2.0"));
+        assert_eq!(
+            header_license("/* SPDX-License-Identifier: MIT */\n"),
+            Some("MIT")
+        );
+        assert_eq!(header_license("fn main() {}\n"), None);
+
+        assert_eq!(
+            with_header(file, "MIT"),
+            "// SPDX-License-Identifier: MIT\n\nfn main() {}\n"
+        );
+        assert_eq!(
+            with_header("#![no_std]\n", "MIT"),
+            "// SPDX-License-Identifier: MIT\n#![no_std]\n"
+        );
+        assert_eq!(
+            with_header("#!/usr/bin/env rust-script\nfn main() {}\n", "MIT"),
+            "#!/usr/bin/env rust-script\n// SPDX-License-Identifier: MIT\nfn main() {}\n"
+        );
+        // The line endings and the missing final newline are kept.
+        assert_eq!(
+            with_header("//! Docs.\r\nfn main() {}", "MIT"),
+            "// SPDX-License-Identifier: MIT\r\n//! Docs.\r\nfn main() {}"
+        );
+        assert_eq!(
+            with_header(
+                "// SPDX-License-Identifier: Apache-2.0\r\nfn main() {}",
+                "MIT"
+            ),
+            "// SPDX-License-Identifier: MIT\r\nfn main() {}"
+        );
+    }
+
+    #[test]
+    fn headers_are_compared_as_expressions() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src).unwrap();
+        let write = |file: &str, license: &str| {
+            let contents = format!("// SPDX-License-Identifier: {}\n", license);
+            std::fs::write(src.join(file), contents).unwrap();
+        };
+        write("lib.rs", "Apache-2.0 OR MIT");
+        write("main.rs", "MIT/Apache-2.0");
+        write("ffi.rs", "MIT AND Apache-2.0");
+        std::fs::write(src.join("util.rs"), "fn f() {}\n").unwrap();
+        assert_eq!(
+            scan_headers(dir.path(), "MIT OR Apache-2.0").unwrap(),
+            [
+                HeaderIssue::Mismatch(
+                    PathBuf::from("src/ffi.rs"),
+                    "MIT AND Apache-2.0".to_string()
+                ),
+                HeaderIssue::Missing(PathBuf::from("src/util.rs")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use std::path::{Path, PathBuf};
+
+use cargo_toml::{Inheritable, Package};
+use serde::Deserialize;
+
+use crate::audit;
+use crate::categories::{self, is_category};
+use crate::error::Result;
+use crate::license::normalize_license;
+use crate::manifest::ManifestFile;
+use crate::policy::Policy;
+use crate::report::Diagnostic;
+use crate::reuse;
+
+/// The fields that are always validated, in the order of the findings.
+pub(crate) const CHECKED_FIELDS: &[&str] = &[
+    "description",
+    "license",
+    "repository",
+    "rust-version",
+    "keywords",
+    "categories",
+];
+
+/// The maximum number of keywords that crates.io accepts.
+pub(crate) const MAX_KEYWORDS: usize = 5;
+
+/// The maximum length of a keyword that crates.io accepts.
+const MAX_KEYWORD_LENGTH: usize = 20;
+
+/// The maximum number of categories that crates.io accepts.
+pub(crate) const MAX_CATEGORIES: usize = 5;
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    /// The finding is not reported.
+    Allow,
+    Warning,
+    /// The finding fails the check.
+    Error,
+}
+
+/// The part of a field that a finding points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// The key, or the table header if the key is missing.
+    Key,
+    Value,
+    /// An element of an array value.
+    Element(usize),
+}
+
+/// A missing or invalid field found by the check.
+#[derive(Debug)]
+pub(crate) struct Finding {
+    pub(crate) field: String,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+    pub(crate) target: Target,
+    /// A suggestion for fixing the finding.
+    pub(crate) help: Option<String>,
+}
+
+impl Finding {
+    pub(crate) fn new(field: &str, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            severity,
+            message: message.into(),
+            target: Target::Key,
+            help: None,
+        }
+    }
+
+    pub(crate) fn at(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub(crate) fn help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    fn missing(field: &str, severity: Severity) -> Self {
+        Finding::new(field, severity, format!("the `{}` field is missing", field)).help(format!(
+            "run `cargo fill` or add `{}` to the `[package]` table",
+            field
+        ))
+    }
+}
+
+pub(crate) fn validate_rust_version(version: &str) -> Result<(), String> {
+    let parts: Vec<&str> = version.split('.').collect();
+    let is_number = |part: &&str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if parts.len() > 3 || !parts.iter().all(is_number) {
+        return Err(format!(
+            "`{}` is not a valid Rust version, expected e.g. `1.70` or `1.70.0`",
+            version
+        ));
+    }
+    Ok(())
+}
+
+/// Trims and lowercases the keywords, dropping the empty and the duplicate ones.
+pub(crate) fn normalize_keywords(keywords: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for keyword in keywords {
+        let keyword = keyword.trim().to_lowercase();
+        if !keyword.is_empty() && !normalized.contains(&keyword) {
+            normalized.push(keyword);
+        }
+    }
+    normalized
+}
+
+/// Checks the keyword against the rules of crates.io.
+pub(crate) fn validate_keyword(keyword: &str) -> Result<(), String> {
+    if keyword.chars().count() > MAX_KEYWORD_LENGTH {
+        return Err(format!(
+            "the keyword `{}` is longer than {} characters",
+            keyword, MAX_KEYWORD_LENGTH
+        ));
+    }
+    if !keyword.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "the keyword `{}` doesn't start with a letter",
+            keyword
+        ));
+    }
+    if let Some(c) = keyword
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !"_-+".contains(*c))
+    {
+        return Err(format!(
+            "the keyword `{}` contains `{}`, only ASCII letters, digits, `_`, `-` and `+` are allowed",
+            keyword, c
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the keywords against the rules of crates.io, explaining every rejected keyword.
+pub(crate) fn validate_keywords(keywords: &[String]) -> Result<(), String> {
+    let mut errors: Vec<String> = keywords
+        .iter()
+        .filter_map(|keyword| validate_keyword(keyword).err())
+        .collect();
+    if keywords.len() > MAX_KEYWORDS {
+        errors.push(format!(
+            "too many keywords ({} > {})",
+            keywords.len(),
+            MAX_KEYWORDS
+        ));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+pub(crate) fn validate_category(category: &str) -> Result<(), String> {
+    if is_category(category) {
+        return Ok(());
+    }
+    let mut message = format!("unknown category `{}`", category);
+    if let Some(suggestion) = categories::suggest(category) {
+        message.push_str(&format!(", did you mean `{}`?", suggestion));
+    }
+    Err(message)
+}
+
+pub(crate) fn validate_categories(categories: &[String]) -> Result<(), String> {
+    if categories.len() > MAX_CATEGORIES {
+        return Err(format!(
+            "too many categories ({} > {})",
+            categories.len(),
+            MAX_CATEGORIES
+        ));
+    }
+    categories
+        .iter()
+        .try_for_each(|category| validate_category(category))
+}
+
+/// Validates the metadata of the package in the manifest without prompting.
+///
+/// The severities can be overridden in the `[severity]` table of the policy.
+pub(crate) fn validate(file: &ManifestFile, package: &Package, policy: &Policy) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    // Only the first finding for the same part of a field is reported.
+    let mut report = |mut finding: Finding| {
+        finding.severity = policy.severity(&finding.field).unwrap_or(finding.severity);
+        let is_duplicate = findings
+            .iter()
+            .any(|f: &Finding| f.field == finding.field && f.target == finding.target);
+        if finding.severity != Severity::Allow && !is_duplicate {
+            findings.push(finding);
+        }
+    };
+
+    // The fields required by the policy come first to take precedence. `Package` has
+    // defaults for some fields, e.g. `edition`, so the manifest itself is checked.
+    for field in policy.required() {
+        if file.package_item(field).is_none() {
+            report(Finding::missing(field, Severity::Error));
+        }
+    }
+    if package.description.is_none() {
+        report(Finding::missing("description", Severity::Error));
+    }
+    match &package.license {
+        None if package.license_file.is_none() => report(
+            Finding::new(
+                "license",
+                Severity::Error,
+                "neither `license` nor `license-file` is set",
+            )
+            .help("add e.g. `license = \"MIT OR Apache-2.0\"` to the `[package]` table"),
+        ),
+        Some(Inheritable::Set(license)) => match normalize_license(license) {
+            Err(e) => {
+                let finding = Finding::new("license", Severity::Error, e.message).at(Target::Value);
+                report(match e.suggestion {
+                    Some(suggestion) => finding.help(format!("did you mean `{}`?", suggestion)),
+                    None => finding.help("see <https://spdx.org/licenses> for the identifiers"),
+                });
+            }
+            Ok(normalized) => {
+                if let Err(e) = policy.check_license(&normalized) {
+                    report(
+                        Finding::new("license", Severity::Error, e.to_string()).at(Target::Value),
+                    );
+                }
+                if normalized != *license {
+                    report(
+                        Finding::new(
+                            "license",
+                            Severity::Warning,
+                            format!(
+                                "`{}` is a legacy or deprecated SPDX license expression",
+                                license
+                            ),
+                        )
+                        .at(Target::Value)
+                        .help(format!("use `{}`", normalized)),
+                    );
+                }
+            }
+        },
+        _ => {}
+    }
+    match &package.repository {
+        None => report(Finding::missing("repository", Severity::Warning)),
+        Some(Inheritable::Set(repository)) => {
+            if let Err(e) = policy.check_repository(repository) {
+                let prefix = policy.repository_prefix().unwrap_or_default();
+                report(
+                    Finding::new("repository", Severity::Error, e.to_string())
+                        .at(Target::Value)
+                        .help(format!("did you mean `{}{}`?", prefix, package.name)),
+                );
+            }
+        }
+        _ => {}
+    }
+    if let Some(Inheritable::Set(version)) = &package.rust_version {
+        if let Err(e) = validate_rust_version(version) {
+            report(
+                Finding::new("rust-version", Severity::Error, e)
+                    .at(Target::Value)
+                    .help("run `cargo fill --rust-version-from-msrv` to find the minimal supported version"),
+            );
+        }
+    }
+    if let Inheritable::Set(keywords) = &package.keywords {
+        if keywords.len() > MAX_KEYWORDS {
+            report(
+                Finding::new(
+                    "keywords",
+                    Severity::Error,
+                    format!("too many keywords ({} > {})", keywords.len(), MAX_KEYWORDS),
+                )
+                .at(Target::Element(MAX_KEYWORDS))
+                .help(format!("keep at most {} keywords", MAX_KEYWORDS)),
+            );
+        }
+        for (i, keyword) in keywords.iter().enumerate() {
+            if let Err(e) = validate_keyword(keyword) {
+                report(
+                    Finding::new("keywords", Severity::Error, e)
+                        .at(Target::Element(i))
+                        .help("see <https://doc.rust-lang.org/cargo/reference/manifest.html#the-keywords-field> for the rules"),
+                );
+            }
+        }
+    }
+    if let Inheritable::Set(categories) = &package.categories {
+        if categories.len() > MAX_CATEGORIES {
+            report(
+                Finding::new(
+                    "categories",
+                    Severity::Error,
+                    format!(
+                        "too many categories ({} > {})",
+                        categories.len(),
+                        MAX_CATEGORIES
+                    ),
+                )
+                .at(Target::Element(MAX_CATEGORIES))
+                .help(format!("keep at most {} categories", MAX_CATEGORIES)),
+            );
+        }
+        for (i, category) in categories.iter().enumerate() {
+            if is_category(category) {
+                continue;
+            }
+            let mut finding = Finding::new(
+                "categories",
+                Severity::Error,
+                format!("unknown category `{}`", category),
+            )
+            .at(Target::Element(i));
+            finding = match categories::suggest(category) {
+                Some(suggestion) => finding.help(format!("did you mean `{}`?", suggestion)),
+                None => finding.help("see <https://crates.io/category_slugs> for the categories"),
+            };
+            report(finding);
+        }
+    }
+    findings
+}
+
+/// Audits the licenses of the dependencies of the package.
+fn audit_findings(
+    package_name: &str,
+    license: &str,
+    dir: &Path,
+    policy: &Policy,
+) -> Result<Vec<Finding>> {
+    let severity = policy.severity("license").unwrap_or(Severity::Error);
+    if severity == Severity::Allow {
+        return Ok(Vec::new());
+    }
+    let audit = audit::audit(package_name, license, dir, policy)?;
+    let findings = audit
+        .violations
+        .iter()
+        .map(|violation| {
+            let help = if policy.allowed_dependency_licenses().is_some() {
+                "replace the dependency or allow its license in `allowed-dependency-licenses`"
+            } else {
+                "replace the dependency or relicense the package"
+            };
+            // The dev-dependencies aren't shipped with the package.
+            let severity = if violation.is_dev_only {
+                Severity::Warning
+            } else {
+                severity
+            };
+            Finding::new("license", severity, violation.message())
+                .at(Target::Value)
+                .help(help)
+        })
+        .collect();
+    Ok(findings)
+}
+
+/// Reports the source files whose SPDX header is missing or disagrees with the license.
+fn header_findings(license: &str, dir: &Path, policy: &Policy) -> Result<Vec<Finding>> {
+    let severity = policy.severity("license").unwrap_or(Severity::Warning);
+    if severity == Severity::Allow {
+        return Ok(Vec::new());
+    }
+    let findings = reuse::scan_headers(dir, license)?
+        .iter()
+        .map(|issue| {
+            Finding::new("license", severity, issue.message())
+                .at(Target::Value)
+                .help(format!(
+                    "add `// SPDX-License-Identifier: {}` at the top of the file",
+                    license
+                ))
+        })
+        .collect();
+    Ok(findings)
+}
+
+/// Validates the packages and locates the findings in their manifests.
+pub(crate) fn diagnostics(manifest_paths: &[PathBuf]) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    for path in manifest_paths {
+        let file = ManifestFile::read(path)?;
+        let Some(package) = file.manifest()?.package else {
+            continue;
+        };
+        let dir = path.parent().expect("the manifest path has no parent");
+        let policy = Policy::find(dir)?;
+        let mut findings = validate(&file, &package, &policy);
+        if let Some(Inheritable::Set(license)) = &package.license {
+            findings.extend(audit_findings(&package.name, license, dir, &policy)?);
+            if reuse::is_enabled(dir, &policy) {
+                findings.extend(header_findings(license, dir, &policy)?);
+            }
+        }
+        for finding in findings {
+            diagnostics.push(Diagnostic::locate(&file, &["package"], finding));
+        }
+    }
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords_are_validated() {
+        let keywords = |keywords: &[&str]| -> Vec<String> {
+            keywords.iter().map(|keyword| keyword.to_string()).collect()
+        };
+        assert_eq!(
+            normalize_keywords(&keywords(&[" CLI", "cli", "", "parser "])),
+            ["cli", "parser"]
+        );
+        assert!(validate_keywords(&keywords(&["cli", "c++", "no_std", "x86-64"])).is_ok());
+        assert!(validate_keyword("a-very-long-keyword-indeed").is_err());
+        assert!(validate_keyword("3d").is_err());
+        assert!(validate_keyword("café").is_err());
+        assert_eq!(
+            validate_keywords(&keywords(&["3d", "a b"])).unwrap_err(),
+            "the keyword `3d` doesn't start with a letter\n\
+            the keyword `a b` contains ` `, only ASCII letters, digits, `_`, `-` and `+` are allowed"
+        );
+    }
+
+    #[test]
+    fn rust_versions_are_validated() {
+        assert!(validate_rust_version("1.70").is_ok());
+        assert!(validate_rust_version("1.70.0").is_ok());
+        assert!(validate_rust_version("1.x").is_err());
+        assert!(validate_rust_version("1.70.0-beta").is_err());
+        assert!(validate_rust_version("1..0").is_err());
+    }
+
+    fn file(contents: &str) -> ManifestFile {
+        ManifestFile::parse(PathBuf::from("Cargo.toml"), contents.to_string()).unwrap()
+    }
+
+    #[test]
+    fn policy_severities_override_the_defaults() {
+        let file = file(
+            r#"
+            [package]
+            name = "example"
+            version = "0.1.0"
+            keywords = ["a", "b", "c", "d", "e", "f"]
+            "#,
+        );
+        let package = file.package().unwrap();
+        let policy = Policy::from_toml(
+            r#"
+            required = ["readme", "edition"]
+            [severity]
+            description = "allow"
+            readme = "warning"
+            "#,
+        )
+        .unwrap();
+        let findings = validate(&file, &package, &policy);
+        let findings: Vec<(&str, Severity)> = findings
+            .iter()
+            .map(|finding| (finding.field.as_str(), finding.severity))
+            .collect();
+        assert_eq!(
+            findings,
+            [
+                ("readme", Severity::Warning),
+                // `Package` defaults `edition` to 2015, but the manifest doesn't set it.
+                ("edition", Severity::Error),
+                ("license", Severity::Error),
+                ("repository", Severity::Warning),
+                ("keywords", Severity::Error),
+            ]
+        );
+    }
+}
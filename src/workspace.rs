@@ -0,0 +1,399 @@
+use std::path::{Path, PathBuf};
+
+use cargo_toml::Package;
+use promptly::prompt_default;
+
+use crate::context::{Context, Filler};
+use crate::error::{Error, Result};
+use crate::fill_rust_version::fill_rust_version;
+use crate::manifest::{is_inherited_value, package_field, to_table, ManifestFile};
+use crate::{fill_authors, fill_edition, fill_homepage, fill_license, fill_repository};
+
+/// The fields that are filled once in `[workspace.package]` and inherited by the members.
+const SHARED_FIELDS: &[(&str, Filler)] = &[
+    ("authors", fill_authors),
+    ("edition", fill_edition),
+    ("rust-version", fill_rust_version),
+    ("homepage", fill_homepage),
+    ("repository", fill_repository),
+    ("license", fill_license),
+];
+
+/// A workspace root and the manifests of its members.
+pub(crate) struct Workspace {
+    pub(crate) root: PathBuf,
+    pub(crate) members: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// Finds the workspace that the manifest belongs to, if any.
+    ///
+    /// Like cargo, this respects the `package.workspace` key and otherwise
+    /// searches the parent directories for a manifest with a `[workspace]` table.
+    pub(crate) fn find(manifest_path: &Path) -> Result<Option<Self>> {
+        let manifest = ManifestFile::read(manifest_path)?.manifest()?;
+        if manifest.workspace.is_some() {
+            return Self::load(manifest_path).map(Some);
+        }
+        let Some(dir) = manifest_path.parent() else {
+            return Ok(None);
+        };
+        if let Some(workspace) = manifest.package.and_then(|package| package.workspace) {
+            return Self::load(&dir.join(workspace).join("Cargo.toml")).map(Some);
+        }
+        for root in dir.ancestors().skip(1).map(|dir| dir.join("Cargo.toml")) {
+            if root.is_file() && ManifestFile::read(&root)?.manifest()?.workspace.is_some() {
+                return Self::load(&root).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Like [`Workspace::find`] but fails if the manifest isn't a part of a workspace.
+    pub(crate) fn find_required(manifest_path: &Path) -> Result<Self> {
+        Self::find(manifest_path)?.ok_or_else(|| {
+            Error::Usage(format!(
+                "{} is not a part of a workspace",
+                manifest_path.display()
+            ))
+        })
+    }
+
+    fn load(root: &Path) -> Result<Self> {
+        let manifest = ManifestFile::read(root)?.manifest()?;
+        let workspace = manifest
+            .workspace
+            .ok_or_else(|| Error::invalid_manifest(root, "no `[workspace]` section"))?;
+        let dir = root.parent().expect("the manifest path has no parent");
+        let exclude: Vec<PathBuf> = workspace.exclude.iter().map(|e| dir.join(e)).collect();
+
+        let mut members = Vec::new();
+        if manifest.package.is_some() {
+            members.push(root.to_path_buf());
+        }
+        for pattern in &workspace.members {
+            let pattern = dir.join(pattern);
+            let pattern = pattern.to_string_lossy();
+            let paths = glob::glob(&pattern).map_err(|e| {
+                Error::invalid_manifest(
+                    root,
+                    format!("invalid workspace member glob `{}`: {}", pattern, e),
+                )
+            })?;
+            for path in paths {
+                let path = path.map_err(|e| {
+                    let path = e.path().to_path_buf();
+                    Error::io(path, e.into())
+                })?;
+                let member = path.join("Cargo.toml");
+                if exclude.iter().any(|e| path.starts_with(e))
+                    || !member.is_file()
+                    || members.contains(&member)
+                {
+                    continue;
+                }
+                members.push(member);
+            }
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            members,
+        })
+    }
+
+    /// Returns the manifests of the members with the given package names.
+    pub(crate) fn select(&self, packages: &[String]) -> Result<Vec<PathBuf>> {
+        let mut names: Vec<(String, &PathBuf)> = Vec::new();
+        for path in &self.members {
+            if let Some(package) = ManifestFile::read(path)?.manifest()?.package {
+                names.push((package.name, path));
+            }
+        }
+        packages
+            .iter()
+            .map(|package| {
+                names
+                    .iter()
+                    .find(|(name, _)| name == package)
+                    .map(|(_, path)| path.to_path_buf())
+                    .ok_or_else(|| {
+                        Error::Usage(format!(
+                            "package `{}` is not a member of the workspace at {}",
+                            package,
+                            self.root.display()
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// Fills the shared fields of `[workspace.package]` and returns the ones that the saved
+    /// root manifest has, or `None` if the changes were declined or only shown with `--dry-run`.
+    pub(crate) fn fill_package(&self, ctx: &Context) -> Result<Option<toml::Table>> {
+        println!(
+            "Filling `[workspace.package]` in {}.\n",
+            self.root.display()
+        );
+        let ctx = &ctx.for_manifest(&self.root)?;
+        let mut file = ManifestFile::read(&self.root)?;
+        let template = file
+            .manifest()?
+            .workspace
+            .and_then(|workspace| workspace.package)
+            .unwrap_or_default();
+        let mut old = to_table(&template);
+        old.retain(|key, _| SHARED_FIELDS.iter().any(|(field, _)| *field == key));
+
+        // The fillers work with packages, so the template is filled as one.
+        let mut package = to_table(&template);
+        package.insert("name".to_string(), "workspace".into());
+        let mut package: Package = package
+            .try_into()
+            .map_err(|e| Error::invalid_manifest(&self.root, e))?;
+
+        let mut new = old.clone();
+        for (field, filler) in SHARED_FIELDS {
+            // Packages have defaults for some fields, e.g. `edition`, so only
+            // the values changed by the filler are taken.
+            let before = package_field(&package, field);
+            ctx.fill_field(&mut package, field, old.get(*field).cloned(), *filler)?;
+            match package_field(&package, field) {
+                after if after == before => {}
+                Some(value) => {
+                    new.insert(field.to_string(), value);
+                }
+                None => {
+                    new.remove(*field);
+                }
+            }
+        }
+        file.update(&["workspace", "package"], &old, &new)?;
+        if !crate::review_and_save_checked(&file, ctx)? {
+            return Ok(None);
+        }
+        self.shared_fields().map(Some)
+    }
+
+    /// Returns the shared fields that `[workspace.package]` of the root manifest sets.
+    fn shared_fields(&self) -> Result<toml::Table> {
+        let template = ManifestFile::read(&self.root)?
+            .manifest()?
+            .workspace
+            .and_then(|workspace| workspace.package)
+            .unwrap_or_default();
+        let mut shared = to_table(&template);
+        shared.retain(|key, _| SHARED_FIELDS.iter().any(|(field, _)| *field == key));
+        Ok(shared)
+    }
+
+    /// Makes the members inherit the given fields of `[workspace.package]`.
+    ///
+    /// The fields that the member leaves unset or sets to the same value are converted.
+    /// The fields with another value are only converted if the user agrees, and never
+    /// with `--yes`, so that e.g. the license of a member isn't changed behind its back.
+    pub(crate) fn inherit(
+        &self,
+        members: &[PathBuf],
+        shared: &toml::Table,
+        ctx: &Context,
+    ) -> Result<()> {
+        for member in members {
+            let mut file = ManifestFile::read(member)?;
+            let package = file.package()?;
+            let mut same = Vec::new();
+            let mut differing = Vec::new();
+            for (field, value) in shared {
+                let current = file
+                    .package_item(field)
+                    .and_then(|_| package_field(&package, field));
+                match current {
+                    Some(current) if is_inherited_value(&current) => {}
+                    Some(current) if current != *value => differing.push((field.as_str(), current)),
+                    _ => same.push(field.as_str()),
+                }
+            }
+            if same.is_empty() && differing.is_empty() {
+                continue;
+            }
+            println!(
+                "{} can inherit the following fields from `[workspace.package]`:\n",
+                member.display()
+            );
+            for field in &same {
+                match file.package_item(field) {
+                    Some(item) => println!("- {} (currently {})", field, item.to_string().trim()),
+                    None => println!("- {} (currently unset)", field),
+                }
+            }
+            let mut replaced = Vec::new();
+            for (field, current) in &differing {
+                println!(
+                    "- {} (currently {}, but {} in the workspace)",
+                    field, current, shared[*field]
+                );
+                if !ctx.is_interactive() {
+                    println!("  Kept, as the values differ.");
+                } else if prompt_default(
+                    format!("  Replace {} with the workspace value?", current),
+                    false,
+                )? {
+                    replaced.push(*field);
+                }
+            }
+            println!();
+            if same.is_empty() && replaced.is_empty() {
+                continue;
+            }
+            file.inherit_package_fields(&same, true)?;
+            file.inherit_package_fields(&replaced, false)?;
+            crate::review_and_save(&file, ctx)?;
+            println!();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::answers::{Answer, Answers};
+    use crate::config::Config;
+
+    use super::*;
+
+    const MEMBER: &str = "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2021\"\n";
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn member(name: &str) -> String {
+        MEMBER.replace("\"a\"", &format!("{:?}", name))
+    }
+
+    fn member_path(dir: &Path, member: &str) -> PathBuf {
+        dir.join(member).join("Cargo.toml")
+    }
+
+    #[test]
+    fn glob_members_are_loaded_without_the_excluded_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Cargo.toml");
+        write(
+            &root,
+            &format!(
+                "{}\n[workspace]\nmembers = [\"crates/*\", \"tools/cli\", \"crates/a\"]\nexclude = [\"crates/old\"]\n",
+                member("root")
+            ),
+        );
+        for name in ["a", "b", "old"] {
+            write(
+                &member_path(&dir.path().join("crates"), name),
+                &member(name),
+            );
+        }
+        write(&member_path(dir.path(), "tools/cli"), &member("cli"));
+        // Directories without a manifest aren't members.
+        std::fs::create_dir_all(dir.path().join("crates/assets")).unwrap();
+
+        let workspace = Workspace::find_required(&root).unwrap();
+        let members: Vec<&Path> = workspace
+            .members
+            .iter()
+            .map(|member| member.strip_prefix(dir.path()).unwrap())
+            .collect();
+        assert_eq!(
+            members,
+            [
+                Path::new("Cargo.toml"),
+                Path::new("crates/a/Cargo.toml"),
+                Path::new("crates/b/Cargo.toml"),
+                Path::new("tools/cli/Cargo.toml"),
+            ]
+        );
+        // The members are found from their own manifests, too.
+        let workspace = Workspace::find_required(&member_path(dir.path(), "tools/cli")).unwrap();
+        assert_eq!(workspace.root, root);
+    }
+
+    #[test]
+    fn members_are_selected_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Cargo.toml");
+        write(&root, "[workspace]\nmembers = [\"a\", \"b\"]\n");
+        write(&member_path(dir.path(), "a"), &member("foo"));
+        write(&member_path(dir.path(), "b"), &member("bar"));
+        let workspace = Workspace::find_required(&root).unwrap();
+
+        let selected = workspace
+            .select(&["bar".to_string(), "foo".to_string()])
+            .unwrap();
+        assert_eq!(
+            selected,
+            [member_path(dir.path(), "b"), member_path(dir.path(), "a")]
+        );
+        // Packages are selected by their names, not by their directories.
+        assert!(matches!(
+            workspace.select(&["a".to_string()]),
+            Err(Error::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn unsaved_workspace_package_is_not_inherited() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Cargo.toml");
+        write(&root, "[workspace]\nmembers = [\"a\"]\n");
+        let member = dir.path().join("a").join("Cargo.toml");
+        write(&member, MEMBER);
+        let workspace = Workspace::find_required(&member).unwrap();
+
+        let mut answers = Answers::default();
+        answers.insert("edition", Answer::Value("2021".to_string()));
+        // A dry run takes the same path as a declined save: nothing is written.
+        let ctx = Context::new(answers.clone(), Config::default(), true, false, true);
+        assert_eq!(workspace.fill_package(&ctx).unwrap(), None);
+        assert_eq!(
+            std::fs::read_to_string(&root).unwrap(),
+            "[workspace]\nmembers = [\"a\"]\n"
+        );
+
+        let ctx = Context::new(answers, Config::default(), true, false, false);
+        let shared = workspace.fill_package(&ctx).unwrap().unwrap();
+        assert_eq!(shared.keys().collect::<Vec<_>>(), ["edition"]);
+        workspace
+            .inherit(&workspace.members, &shared, &ctx)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&member).unwrap(),
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = { workspace = true }\n"
+        );
+    }
+
+    #[test]
+    fn differing_values_are_kept_with_yes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Cargo.toml");
+        write(
+            &root,
+            "[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nlicense = \"Apache-2.0\"\nedition = \"2021\"\nrepository = \"https://github.com/acme/a\"\n",
+        );
+        let member = member_path(dir.path(), "a");
+        let contents = "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2018\"\nlicense = \"MIT\" # legal says MIT only\nrepository = \"https://github.com/acme/a\" # the mirror\n";
+        write(&member, contents);
+        let workspace = Workspace::find_required(&member).unwrap();
+
+        let ctx = Context::new(Answers::default(), Config::default(), true, false, false);
+        let shared = workspace.shared_fields().unwrap();
+        workspace
+            .inherit(&workspace.members, &shared, &ctx)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&member).unwrap(),
+            contents.replace(
+                "\"https://github.com/acme/a\" # the mirror",
+                "{ workspace = true } # the mirror"
+            )
+        );
+    }
+}